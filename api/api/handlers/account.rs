@@ -0,0 +1,48 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
+use crate::models::{AccountBalance, Permission};
+use crate::services::account_service;
+use actix_web::{get, web, HttpResponse};
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "cuenta",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Cuenta del usuario autenticado", body = AccountBalance)),
+)]
+#[get("/balance", wrap = "RequirePermission::new(Permission::AccountRead)")]
+pub async fn balance(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let account = account_service::get_balance(pool.store.as_ref(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(account))
+}
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "cuenta",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Id de la cuenta")),
+    responses(
+        (status = 200, description = "La cuenta, si es del usuario o tiene `account:read_any`", body = AccountBalance),
+        (status = 404, description = "ACCOUNT_NOT_FOUND: no existe o es de otro usuario", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[get("/accounts/{id}", wrap = "RequirePermission::new(Permission::AccountRead)")]
+pub async fn get_account(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, BankError> {
+    let account = account_service::get_account(pool.store.as_ref(), path.into_inner()).await?;
+
+    // Solo el dueño de la cuenta (o quien puede leer cualquier cuenta) puede consultarla. La
+    // cuenta ajena responde igual que la que no existe para no confirmar qué ids hay
+    if account.user_id != user.user_id && !user.has_permission(Permission::AccountReadAny) {
+        return Err(BankError::AccountNotFound);
+    }
+
+    Ok(HttpResponse::Ok().json(account))
+}
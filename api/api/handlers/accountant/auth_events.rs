@@ -0,0 +1,26 @@
+// src/api/handlers/accountant/auth_events.rs
+
+use crate::AppState;
+use crate::errors::BankError;
+use crate::models::{AuthEventQuery, AuthEventRecord};
+use actix_web::{get, web, HttpResponse};
+
+use crate::services::login_throttle;
+
+// Fallos de login, intentos rechazados y bloqueos, los más recientes primero
+#[utoipa::path(
+    context_path = "/accountant",
+    tag = "contador",
+    security(("bearer_auth" = [])),
+    params(AuthEventQuery),
+    responses((status = 200, description = "Eventos de autenticación", body = [AuthEventRecord])),
+)]
+#[get("/auth-events")]
+pub async fn list(
+    pool: web::Data<AppState>,
+    query: web::Query<AuthEventQuery>,
+) -> Result<HttpResponse, BankError> {
+    let events = login_throttle::list_events(pool.store.as_ref(), &query).await?;
+
+    Ok(HttpResponse::Ok().json(events))
+}
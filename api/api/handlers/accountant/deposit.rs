@@ -1,52 +1,44 @@
 use crate::AppState;
-use crate::middleware::jwt_auth::Claims;
-use actix_web::{post, web, HttpResponse, Responder};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use serde_json::json;
-use std::env;
+use crate::api::idempotency;
+use crate::errors::{BankError, ProblemDetails};
+use crate::metrics::MoneyOperation;
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
+use actix_web::{post, web, HttpRequest, HttpResponse};
 
-use crate::models::AccountantData;
+use crate::models::{AccountantData, MessageResponse, Permission};
 use crate::services::accountant::deposit as accountant_deposit;
 
-#[post("/deposit")]
+#[utoipa::path(
+    context_path = "/accountant",
+    tag = "contador",
+    security(("bearer_auth" = [])),
+    params(("Idempotency-Key" = Option<String>, Header, description = "Repetir la petición con la misma clave devuelve la respuesta guardada sin repetir el movimiento")),
+    responses(
+        (status = 200, description = "Depósito realizado: el dinero entra desde la reserva del banco", body = MessageResponse,
+            headers(("Idempotent-Replayed" = String, description = "`true` si la respuesta es la guardada para la Idempotency-Key"))),
+        (status = 400, description = "INVALID_AMOUNT o UNKNOWN_USER", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "IDEMPOTENCY_KEY_REUSED", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/deposit", wrap = "RequirePermission::new(Permission::CashDeposit)")]
 pub async fn deposit(
+    req: HttpRequest,
     pool: web::Data<AppState>,
     data: web::Json<AccountantData>,
-    bearer: BearerAuth,
-) -> impl Responder {
-    println!("DEBUG: Petición de retiro recibida.");
-    let claims = match decode::<Claims>(
-        bearer.token(),
-        &DecodingKey::from_secret(env::var("JWT_SECRET").expect("JWT_SECRET must be set").as_ref()),
-        &Validation::new(jsonwebtoken::Algorithm::default()),
-    ) {
-        Ok(token_data) => token_data.claims,
-        Err(e) => {
-            println!("Error: fallo al decodificar el token {:?}", e);
-            return HttpResponse::Unauthorized().finish();
-        }
-    };
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let idempotency_key = idempotency::idempotency_key(&req, "deposit", &*data)?;
 
-    if claims.role != "accountant" {
-        println!("Error: acceso denegado {}", claims.role);
-        return HttpResponse::Forbidden().json("Acceso denegado. Solo el contador puede realizar esta acción.");
-    }
+    let result = accountant_deposit::process_deposit(
+        pool.store.as_ref(),
+        &pool.config.limits,
+        user.user_id,
+        &data,
+        idempotency_key.as_ref(),
+    )
+    .await;
+    pool.metrics.record_money_operation(MoneyOperation::Deposit, data.amount, &result);
+    let response = result?;
 
-    if data.amount <= 0.0 {
-        println!("Error: Cantidad no valida {}", data.amount);
-        return HttpResponse::BadRequest().json("La cantidad debe ser mayor a 0.");
-    }
-    
-    println!("DEBUG: Llamando al servicio de retiro.");
-
-    match accountant_deposit::process_deposit(&pool.db, claims.sub, &data).await {
-        Ok(_) => {
-        println!("DEBUG: Retiro procesado con éxito.");
-        HttpResponse::Ok().json(json!({
-            "message": "Depósito realizado con éxito"
-        }))
-        },
-        Err(e) => e,
-    }
+    Ok(idempotency::into_response(response))
 }
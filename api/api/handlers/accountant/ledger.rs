@@ -0,0 +1,22 @@
+// src/api/handlers/accountant/ledger.rs
+
+use crate::AppState;
+use crate::errors::BankError;
+use crate::models::LedgerReport;
+use actix_web::{get, web, HttpResponse};
+
+use crate::services::ledger;
+
+// Comprueba que cada asiento cuadra y que los saldos coinciden con los apuntes
+#[utoipa::path(
+    context_path = "/accountant",
+    tag = "contador",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Resultado de la comprobación; `consistent` es falso si algo no cuadra", body = LedgerReport)),
+)]
+#[get("/ledger/check")]
+pub async fn check(pool: web::Data<AppState>) -> Result<HttpResponse, BankError> {
+    let report = ledger::check_invariants(pool.store.as_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -1,2 +1,4 @@
 pub mod deposit;
 pub mod withdraw;
+pub mod ledger;
+pub mod auth_events;
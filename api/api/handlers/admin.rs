@@ -0,0 +1,158 @@
+// src/api/handlers/admin.rs
+
+use crate::AppState;
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::AuthenticatedUser;
+use crate::models::{
+    RoleChange, TemporaryPassword, TwoFactorPolicy, UserCreated, UserData, UserSummary,
+};
+use crate::services::{admin_service, two_factor_service};
+use actix_web::{get, post, put, web, HttpResponse};
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Todos los usuarios, sin datos de acceso", body = [UserSummary]),
+    ),
+)]
+#[get("/users")]
+pub async fn list_users(pool: web::Data<AppState>) -> Result<HttpResponse, BankError> {
+    let users = admin_service::list_users(pool.store.as_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(users))
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Contador creado", body = UserCreated),
+        (status = 400, description = "WEAK_PASSWORD", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "USERNAME_TAKEN", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/accountants")]
+pub async fn create_accountant(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    user_data: web::Json<UserData>,
+) -> Result<HttpResponse, BankError> {
+    let user_id = admin_service::create_user(pool.store.as_ref(), Some(admin.user_id), &user_data, "accountant").await?;
+
+    Ok(HttpResponse::Created().json(UserCreated {
+        message: "Contador creado exitosamente!".to_string(),
+        user_id,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Id del usuario")),
+    responses(
+        (status = 204, description = "Rol cambiado"),
+        (status = 400, description = "UNKNOWN_USER o INVALID_REQUEST: el rol no existe o el administrador es el propio usuario", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[put("/users/{id}/role")]
+pub async fn change_role(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    path: web::Path<i32>,
+    role_change: web::Json<RoleChange>,
+) -> Result<HttpResponse, BankError> {
+    admin_service::change_role(pool.store.as_ref(), admin.user_id, path.into_inner(), &role_change.role).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Id del usuario")),
+    responses(
+        (status = 204, description = "Usuario deshabilitado y sus sesiones cerradas"),
+        (status = 400, description = "UNKNOWN_USER o INVALID_REQUEST: un administrador no puede modificarse a sí mismo", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/users/{id}/disable")]
+pub async fn disable_user(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, BankError> {
+    admin_service::set_disabled(pool.store.as_ref(), admin.user_id, path.into_inner(), true).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Id del usuario")),
+    responses(
+        (status = 204, description = "Usuario habilitado"),
+        (status = 400, description = "UNKNOWN_USER o INVALID_REQUEST: un administrador no puede modificarse a sí mismo", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/users/{id}/enable")]
+pub async fn enable_user(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, BankError> {
+    admin_service::set_disabled(pool.store.as_ref(), admin.user_id, path.into_inner(), false).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = i32, Path, description = "Id del usuario")),
+    responses(
+        (status = 200, description = "Contraseña temporal, que no se vuelve a mostrar; se cierran las sesiones", body = TemporaryPassword),
+        (status = 400, description = "UNKNOWN_USER", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/users/{id}/reset-credentials")]
+pub async fn reset_credentials(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> Result<HttpResponse, BankError> {
+    let temporary_password =
+        admin_service::reset_credentials(pool.store.as_ref(), admin.user_id, path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(TemporaryPassword { temporary_password }))
+}
+
+// Obliga (o deja de obligar) a todos los usuarios de un rol a usar 2FA
+#[utoipa::path(
+    context_path = "/admin",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("role" = String, Path, description = "Nombre del rol")),
+    responses(
+        (status = 204, description = "Política guardada"),
+        (status = 400, description = "INVALID_REQUEST: el rol no existe", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[put("/roles/{role}/two-factor")]
+pub async fn set_two_factor_policy(
+    pool: web::Data<AppState>,
+    admin: AuthenticatedUser,
+    path: web::Path<String>,
+    policy: web::Json<TwoFactorPolicy>,
+) -> Result<HttpResponse, BankError> {
+    two_factor_service::set_role_policy(pool.store.as_ref(), admin.user_id, &path, policy.required).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
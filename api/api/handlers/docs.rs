@@ -0,0 +1,65 @@
+// src/api/handlers/docs.rs
+
+use std::sync::Arc;
+
+use actix_web::{get, web, HttpResponse};
+use utoipa::OpenApi;
+
+use crate::api::openapi::ApiDoc;
+use crate::config::Environment;
+use crate::AppState;
+
+// Swagger UI servido por la propia API (ver `docs_asset`), apuntando a /openapi.json
+const DOCS_PAGE: &str = r##"<!doctype html>
+<html lang="es">
+<head>
+  <meta charset="utf-8">
+  <title>FriendBank API</title>
+  <link rel="stylesheet" href="/docs/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="/docs/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+// El contrato de la API; público también en producción
+#[get("/openapi.json")]
+pub async fn openapi() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+// Documentación interactiva, solo fuera de producción
+#[get("/docs")]
+pub async fn docs(pool: web::Data<AppState>) -> HttpResponse {
+    if pool.config.server.environment == Environment::Production {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(DOCS_PAGE)
+}
+
+// Ficheros de Swagger UI; la versión va fijada en el binario, así la página no depende
+// de lo que sirva un CDN
+#[get("/docs/{file}")]
+pub async fn docs_asset(pool: web::Data<AppState>, file: web::Path<String>) -> HttpResponse {
+    if pool.config.server.environment == Environment::Production {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let config = Arc::new(utoipa_swagger_ui::Config::new(["/openapi.json"]));
+    match utoipa_swagger_ui::serve(&file, config) {
+        Ok(Some(asset)) => HttpResponse::Ok().content_type(asset.content_type).body(asset.bytes.into_owned()),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            tracing::error!(error = %e, file = %file, "no se pudo servir Swagger UI");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// src/api/handlers/health.rs
+
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, web, HttpResponse};
+
+use crate::services::health_service::{self, HealthReport};
+use crate::AppState;
+
+// Sonda de vida: solo comprueba que el proceso atiende peticiones
+#[utoipa::path(
+    tag = "sistema",
+    responses((status = 200, description = "El proceso está vivo", body = HealthReport)),
+)]
+#[get("/healthz")]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(health_service::liveness())
+}
+
+// Sonda de disponibilidad: 503 mientras alguna comprobación falle
+#[utoipa::path(
+    tag = "sistema",
+    responses(
+        (status = 200, description = "Todas las comprobaciones pasan", body = HealthReport),
+        (status = 503, description = "Alguna comprobación falla; el detalle va en `checks`", body = HealthReport),
+    ),
+)]
+#[get("/readyz")]
+pub async fn readyz(pool: web::Data<AppState>) -> HttpResponse {
+    let report = health_service::readiness(pool.store.as_ref()).await;
+
+    let mut response = if report.is_ok() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+
+    response
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .json(report)
+}
@@ -0,0 +1,28 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
+use crate::models::{HistoryPage, HistoryQuery, Permission};
+use crate::services::history_service;
+use actix_web::{get, web, HttpResponse};
+
+// Movimientos del usuario, del más reciente al más antiguo, paginados por cursor
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "cuenta",
+    security(("bearer_auth" = [])),
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Una página de movimientos; `next_cursor` pide la siguiente", body = HistoryPage),
+        (status = 400, description = "INVALID_REQUEST: filtros inválidos", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[get("/transactions", wrap = "RequirePermission::new(Permission::AccountRead)")]
+pub async fn transactions(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    query: web::Query<HistoryQuery>,
+) -> Result<HttpResponse, BankError> {
+    let page =
+        history_service::list_transactions(pool.store.as_ref(), &pool.config.limits, user.user_id, &query).await?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
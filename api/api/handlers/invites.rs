@@ -0,0 +1,40 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
+use crate::models::{InviteCode, Permission};
+use crate::services::registration_service;
+use actix_web::{get, post, web, HttpResponse};
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "registro",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Código de invitación nuevo", body = InviteCode),
+        (status = 400, description = "INVALID_REQUEST: ya tiene el máximo de invitaciones activas", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/invites", wrap = "RequirePermission::new(Permission::InvitesCreate)")]
+pub async fn create_invite(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let invite = registration_service::create_invite(pool.store.as_ref(), &pool.config.registration, user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(invite))
+}
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "registro",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Invitaciones creadas por el usuario", body = [InviteCode])),
+)]
+#[get("/invites", wrap = "RequirePermission::new(Permission::InvitesCreate)")]
+pub async fn list_invites(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let invites = registration_service::list_invites(pool.store.as_ref(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(invites))
+}
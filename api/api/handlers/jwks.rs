@@ -0,0 +1,20 @@
+// src/api/handlers/jwks.rs
+
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, web, HttpResponse};
+
+use crate::services::jwt_keys::JwkSet;
+use crate::AppState;
+
+// Público y cacheable: tras publicar una clave nueva conviene esperar a que caduque la
+// caché de los clientes antes de empezar a firmar con ella
+#[utoipa::path(
+    tag = "sistema",
+    responses((status = 200, description = "Claves públicas con las que se verifican los access tokens", body = JwkSet)),
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(pool: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(300)]))
+        .json(pool.tokens.keys.jwks())
+}
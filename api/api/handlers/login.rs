@@ -1,44 +1,134 @@
-use crate::models::UserData;
-use actix_web::{post, web, HttpResponse, Responder};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use chrono::Utc;
-use std::env;
-
-use crate::services::user_service;
-
-#[derive(serde::Serialize, serde::Deserialize)]
-pub struct Claims {
-    pub sub: i32,
-    pub exp: u64,
-    pub role: String,
+use crate::AppState;
+use crate::errors::{BankError, ProblemDetails};
+use crate::models::{
+    LoginOutcome, LoginResponse, SessionClient, TwoFactorChallenge, TwoFactorLogin, User, UserData,
+};
+use actix_web::http::header;
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use chrono::Duration;
+use std::net::IpAddr;
+
+use crate::services::{token_service, two_factor_service, user_service};
+
+// Dispositivo e IP que se guardan con la sesión
+fn session_client(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> SessionClient {
+    SessionClient {
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(255).collect()),
+        ip_address: client_ip(req, trusted_proxies),
+    }
+}
+
+// Detrás de un proxy de confianza la conexión llega desde el proxy. Cada proxy añade a
+// X-Forwarded-For la IP de quien le habló, así que el cliente es la última que no es de un
+// proxy propio; las anteriores las pudo escribir él. Sin esa cabecera la IP es desconocida
+// y el login no se limita por IP, en lugar de meter a todos los clientes en la del proxy.
+pub(crate) fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer.to_string());
+    }
+
+    let forwarded: Vec<&str> = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    forwarded
+        .into_iter()
+        .rev()
+        .map(|ip| ip.parse::<IpAddr>().ok())
+        .find(|ip| !matches!(ip, Some(ip) if trusted_proxies.contains(ip)))?
+        .map(|ip| ip.to_string())
+}
+
+// Respuesta común del login completo, con o sin segundo factor
+async fn login_response(req: &HttpRequest, state: &AppState, user: &User) -> Result<HttpResponse, BankError> {
+    let client = session_client(req, &state.config.server.trusted_proxies);
+    let tokens = token_service::issue_tokens(state.store.as_ref(), &state.tokens, user, &client).await?;
+    let enrollment_required = two_factor_service::enrollment_required(state.store.as_ref(), user).await?;
+
+    Ok(HttpResponse::Ok().json(LoginResponse {
+        message: "Login exitoso".to_string(),
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+        role: user.role.clone(),
+        two_factor_enrollment_required: enrollment_required,
+    }))
 }
 
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Tokens de la nueva sesión o, con 2FA activada, un desafío para /auth/login/2fa", body = LoginOutcome),
+        (status = 401, description = "INVALID_CREDENTIALS", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "USER_DISABLED", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "TOO_MANY_ATTEMPTS: usuario o IP bloqueados temporalmente", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = i64, description = "Segundos hasta el siguiente intento"))),
+    ),
+)]
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     pool: web::Data<crate::AppState>,
     user_data: web::Json<UserData>,
-) -> impl Responder {
-    let user = match user_service::verify_login(&pool.db, &user_data).await {
-        Ok(u) => u,
-        Err(e) => return e,
-    };
-    
-    let claims = Claims {
-        sub: user.id,
-        exp: (Utc::now() + chrono::Duration::hours(2)).timestamp() as u64,
-        role: user.role.clone(),
-    };
-
-    let secret_key = env::var("JWT_SECRET")
-        .expect("JWT_SECRET must be set in .env file");
-    let token = match encode(&Header::default(), &claims, &EncodingKey::from_secret(secret_key.as_ref())) {
-        Ok(t) => t,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
-
-    HttpResponse::Ok().json(serde_json::json!({
-        "message": "Login exitoso",
-        "token": token,
-        "role": user.role,
-    }))
+) -> Result<HttpResponse, BankError> {
+    // Los fallos también se cuentan por IP para frenar ataques contra muchos usuarios
+    let client_ip = client_ip(&req, &pool.config.server.trusted_proxies);
+    let user = user_service::verify_login(pool.store.as_ref(), &pool.config.limits, &user_data, client_ip.as_deref())
+        .await
+        .inspect_err(|e| pool.metrics.record_failed_login(e))?;
+
+    // Con 2FA activada, la contraseña solo da un desafío para /auth/login/2fa
+    if two_factor_service::is_enabled(pool.store.as_ref(), user.id).await? {
+        let challenge = two_factor_service::create_challenge(pool.store.as_ref(), user.id).await?;
+
+        return Ok(HttpResponse::Ok().json(TwoFactorChallenge {
+            message: "Introduce el código de verificación".to_string(),
+            two_factor_required: true,
+            challenge,
+            expires_in: Duration::minutes(two_factor_service::CHALLENGE_TTL_MINUTES).num_seconds(),
+        }));
+    }
+
+    login_response(&req, &pool, &user).await
+}
+
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Tokens de la nueva sesión", body = LoginResponse),
+        (status = 401, description = "INVALID_TWO_FACTOR_CODE: código incorrecto o desafío caducado", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "USER_DISABLED", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "TOO_MANY_ATTEMPTS: usuario o IP bloqueados temporalmente", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = i64, description = "Segundos hasta el siguiente intento"))),
+    ),
+)]
+#[post("/login/2fa")]
+pub async fn login_two_factor(
+    req: HttpRequest,
+    pool: web::Data<crate::AppState>,
+    data: web::Json<TwoFactorLogin>,
+) -> Result<HttpResponse, BankError> {
+    let client_ip = client_ip(&req, &pool.config.server.trusted_proxies);
+    let user = two_factor_service::complete_login(
+        pool.store.as_ref(),
+        &pool.config.limits,
+        &data.challenge,
+        &data.code,
+        client_ip.as_deref(),
+    )
+    .await
+    .inspect_err(|e| pool.metrics.record_failed_login(e))?;
+
+    login_response(&req, &pool, &user).await
 }
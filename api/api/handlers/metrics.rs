@@ -0,0 +1,42 @@
+// src/api/handlers/metrics.rs
+
+use actix_web::{get, web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+
+use crate::config::Environment;
+use crate::errors::{BankError, ProblemDetails};
+use crate::services::token_service::hash_token;
+use crate::AppState;
+
+// Formato de texto de Prometheus. Con `server.metrics_token` se exige ese token como
+// Bearer; sin él, el endpoint solo existe fuera de producción
+#[utoipa::path(
+    tag = "sistema",
+    responses(
+        (status = 200, description = "Métricas en el formato de texto de Prometheus", body = String, content_type = "text/plain"),
+        (status = 401, description = "UNAUTHORIZED: falta el token de métricas o no es válido", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Métricas desactivadas: producción sin token configurado"),
+    ),
+)]
+#[get("/metrics")]
+pub async fn metrics(pool: web::Data<AppState>, bearer: Option<BearerAuth>) -> Result<HttpResponse, BankError> {
+    match &pool.config.server.metrics_token {
+        // Se comparan los hashes para que el tiempo no dependa del prefijo acertado
+        Some(expected) => {
+            let given = bearer.as_ref().map(|bearer| hash_token(bearer.token()));
+            if given.as_deref() != Some(hash_token(expected).as_str()) {
+                return Err(BankError::Unauthorized);
+            }
+        }
+        None if pool.config.server.environment == Environment::Production => {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+        None => {}
+    }
+
+    let body = pool.metrics.render(pool.store.pool_stats())?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(body))
+}
@@ -2,3 +2,15 @@ pub mod login;
 pub mod signup;
 pub mod transaction;
 pub mod accountant;
+pub mod account;
+pub mod history;
+pub mod session;
+pub mod admin;
+pub mod register;
+pub mod invites;
+pub mod password;
+pub mod two_factor;
+pub mod jwks;
+pub mod health;
+pub mod metrics;
+pub mod docs;
@@ -0,0 +1,92 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::AuthenticatedUser;
+use crate::models::{MessageResponse, PasswordChange, PasswordResetConfirm, PasswordResetRequest};
+use crate::services::password_service;
+use actix_web::{post, web, HttpRequest, HttpResponse};
+
+use super::login::client_ip;
+
+// Cambia la contraseña del usuario autenticado y cierra todas sus sesiones
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "cuenta",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Contraseña cambiada; hay que volver a iniciar sesión"),
+        (status = 400, description = "WEAK_PASSWORD", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "INVALID_CREDENTIALS: la contraseña actual no es correcta", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "TOO_MANY_ATTEMPTS: usuario o IP bloqueados temporalmente", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = i64, description = "Segundos hasta el siguiente intento"))),
+    ),
+)]
+#[post("/password")]
+pub async fn change_password(
+    req: HttpRequest,
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    data: web::Json<PasswordChange>,
+) -> Result<HttpResponse, BankError> {
+    let client_ip = client_ip(&req, &pool.config.server.trusted_proxies);
+    password_service::change_password(
+        pool.store.as_ref(),
+        &pool.config.limits,
+        user.user_id,
+        &data.current_password,
+        &data.new_password,
+        client_ip.as_deref(),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Responde 202 exista o no el usuario; 429 si el usuario o la IP superan el límite
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    responses(
+        (status = 202, description = "Si el usuario existe, se le envía el token por el notificador", body = MessageResponse),
+        (status = 429, description = "TOO_MANY_ATTEMPTS: usuario o IP bloqueados temporalmente", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = i64, description = "Segundos hasta el siguiente intento"))),
+    ),
+)]
+#[post("/password/forgot")]
+pub async fn forgot_password(
+    req: HttpRequest,
+    pool: web::Data<crate::AppState>,
+    data: web::Json<PasswordResetRequest>,
+) -> Result<HttpResponse, BankError> {
+    let client_ip = client_ip(&req, &pool.config.server.trusted_proxies);
+    password_service::request_reset(
+        pool.store.as_ref(),
+        pool.notifier.as_ref(),
+        &pool.config.auth,
+        &pool.config.limits,
+        &data.username,
+        client_ip.as_deref(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Accepted().json(MessageResponse::new(
+        "Si el usuario existe, recibirá un código para restablecer la contraseña.",
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    responses(
+        (status = 204, description = "Contraseña restablecida; se cierran todas las sesiones"),
+        (status = 400, description = "INVALID_RESET_TOKEN o WEAK_PASSWORD", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "USER_DISABLED", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/password/reset")]
+pub async fn reset_password(
+    pool: web::Data<crate::AppState>,
+    data: web::Json<PasswordResetConfirm>,
+) -> Result<HttpResponse, BankError> {
+    password_service::reset_password(pool.store.as_ref(), &data.token, &data.new_password).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,26 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::models::{RegisterRequest, UserCreated};
+use crate::services::registration_service;
+use actix_web::{post, web, HttpResponse};
+
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "registro",
+    responses(
+        (status = 201, description = "Usuario registrado con el rol `user`", body = UserCreated),
+        (status = 400, description = "INVALID_REQUEST, INVALID_INVITE_CODE o WEAK_PASSWORD", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "USERNAME_TAKEN", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/register")]
+pub async fn register(
+    pool: web::Data<crate::AppState>,
+    request: web::Json<RegisterRequest>,
+) -> Result<HttpResponse, BankError> {
+    let user_id = registration_service::register(pool.store.as_ref(), &pool.config.registration, &request).await?;
+
+    Ok(HttpResponse::Created().json(UserCreated {
+        message: "Usuario registrado exitosamente!".to_string(),
+        user_id,
+    }))
+}
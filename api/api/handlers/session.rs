@@ -0,0 +1,116 @@
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::AuthenticatedUser;
+use crate::models::{LogoutRequest, RefreshRequest, RefreshedTokens};
+use actix_web::{delete, get, post, web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+
+use crate::services::session_service::{self, SessionSummary};
+use crate::services::token_service;
+
+// Rota el refresh token: el usado deja de valer y reutilizarlo cierra la sesión
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Access token y refresh token nuevos", body = RefreshedTokens),
+        (status = 401, description = "UNAUTHORIZED: refresh token inválido, caducado o reutilizado", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "USER_DISABLED", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/refresh")]
+pub async fn refresh(
+    pool: web::Data<crate::AppState>,
+    refresh_data: web::Json<RefreshRequest>,
+) -> Result<HttpResponse, BankError> {
+    let tokens = token_service::refresh(pool.store.as_ref(), &pool.tokens, &refresh_data.refresh_token).await?;
+
+    Ok(HttpResponse::Ok().json(RefreshedTokens {
+        token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in,
+    }))
+}
+
+// `/auth` no pasa por el middleware, así que el access token se valida aquí
+#[utoipa::path(
+    context_path = "/auth",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    request_body(content = Option<LogoutRequest>, description = "El refresh token de la sesión, para revocarlo también"),
+    responses((status = 204, description = "Sesión cerrada")),
+)]
+#[post("/logout")]
+pub async fn logout(
+    pool: web::Data<crate::AppState>,
+    bearer: BearerAuth,
+    logout_data: Option<web::Json<LogoutRequest>>,
+) -> Result<HttpResponse, BankError> {
+    let claims = token_service::decode_access_token(&pool.tokens, bearer.token())?;
+
+    if token_service::is_revoked(pool.store.as_ref(), &claims.jti).await? {
+        return Err(BankError::Unauthorized);
+    }
+
+    let refresh_token = logout_data
+        .as_ref()
+        .and_then(|data| data.refresh_token.as_deref());
+
+    token_service::logout(pool.store.as_ref(), &claims, refresh_token).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Sesiones abiertas del usuario, con el dispositivo y la IP desde la que se abrieron
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "sesiones",
+    security(("bearer_auth" = [])),
+    responses((status = 200, description = "Sesiones abiertas; `current` marca la del token usado", body = [SessionSummary])),
+)]
+#[get("/sessions")]
+pub async fn list_sessions(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let sessions = session_service::list_sessions(pool.store.as_ref(), user.user_id, &user.session_id).await?;
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "sesiones",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Id de la sesión")),
+    responses(
+        (status = 204, description = "Sesión cerrada"),
+        (status = 404, description = "SESSION_NOT_FOUND", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[delete("/sessions/{id}")]
+pub async fn terminate_session(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    path: web::Path<String>,
+) -> Result<HttpResponse, BankError> {
+    session_service::terminate_session(pool.store.as_ref(), user.user_id, &path).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Cierra todas las sesiones, también la del token usado en la petición
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "sesiones",
+    security(("bearer_auth" = [])),
+    responses((status = 204, description = "Sesiones cerradas")),
+)]
+#[delete("/sessions")]
+pub async fn terminate_all_sessions(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    session_service::terminate_all_sessions(pool.store.as_ref(), user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
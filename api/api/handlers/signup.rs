@@ -1,54 +1,29 @@
-use crate::models::UserData;
-use actix_web::{post, web, HttpResponse, Responder};
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
+use crate::models::{Permission, UserCreated, UserData};
+use crate::services::admin_service;
+use actix_web::{post, web, HttpResponse};
 
-
-#[post("/signup")]
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Cliente creado", body = UserCreated),
+        (status = 400, description = "WEAK_PASSWORD", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "USERNAME_TAKEN", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/signup", wrap = "RequirePermission::new(Permission::UsersManage)")]
 pub async fn signup(
     pool: web::Data<crate::AppState>,
+    admin: AuthenticatedUser,
     user_data: web::Json<UserData>,
-) -> impl Responder {
-    let mut transaction = match pool.db.begin().await {
-        Ok(tx) => tx,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
-
-    let hashed_password = match bcrypt::hash(&user_data.password, 10) {
-        Ok(hash) => hash,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+) -> Result<HttpResponse, BankError> {
+    let user_id = admin_service::create_user(pool.store.as_ref(), Some(admin.user_id), &user_data, "user").await?;
 
-    let user_id: i32 = match sqlx::query!(
-        "INSERT INTO users (username, password_hash) VALUES (?, ?)",
-        user_data.username,
-        hashed_password
-    )
-    .execute(&mut *transaction)
-    .await {
-        Ok(result) => result.last_insert_id() as i32,
-        Err(_) => {
-            return HttpResponse::InternalServerError().finish();
-        }
-    };
-    
-    match sqlx::query!(
-        "INSERT INTO accounts (user_id, balance) VALUES (?, 0)",
-        user_id
-    )
-    .execute(&mut *transaction)
-    .await {
-        Ok(_) => {
-            // 5. Si todo es exitoso, confirmar la transacción
-            if transaction.commit().await.is_ok() {
-                HttpResponse::Ok().json(serde_json::json!({
-                    "message": "Usuario creado exitosamente!"
-                }))
-            } else {
-                HttpResponse::InternalServerError().finish()
-            }
-        },
-        Err(_) => {
-            // 6. Si falla la inserción de la cuenta, la transacción se revierte
-            HttpResponse::InternalServerError().finish()
-        }
-    }
+    Ok(HttpResponse::Created().json(UserCreated {
+        message: "Usuario creado exitosamente!".to_string(),
+        user_id,
+    }))
 }
@@ -1,53 +1,45 @@
+use crate::api::idempotency;
+use crate::errors::{BankError, ProblemDetails};
+use crate::metrics::MoneyOperation;
+use crate::middleware::rbac::{AuthenticatedUser, RequirePermission};
 use crate::services::transaction_service;
-use actix_web::{post, web, HttpResponse, Responder};
-use actix_web_httpauth::extractors::bearer::BearerAuth;
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use std::env;
+use actix_web::{post, web, HttpRequest, HttpResponse};
 
 // Importa el servicio y la estructura de datos
-use crate::models::TransactionData;
-use crate::middleware::jwt_auth::Claims;
+use crate::models::{MessageResponse, Permission, TransactionData};
 
-#[post("/transfer")]
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "cuenta",
+    security(("bearer_auth" = [])),
+    params(("Idempotency-Key" = Option<String>, Header, description = "Repetir la petición con la misma clave devuelve la respuesta guardada sin transferir otra vez")),
+    responses(
+        (status = 200, description = "Transferencia realizada", body = MessageResponse,
+            headers(("Idempotent-Replayed" = String, description = "`true` si la respuesta es la guardada para la Idempotency-Key"))),
+        (status = 400, description = "INVALID_AMOUNT, INSUFFICIENT_FUNDS, UNKNOWN_RECIPIENT o SELF_TRANSFER", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "IDEMPOTENCY_KEY_REUSED", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/transfer", wrap = "RequirePermission::new(Permission::TransferCreate)")]
 pub async fn transfer(
+    req: HttpRequest,
     pool: web::Data<crate::AppState>,
     transaction_data: web::Json<TransactionData>,
-    bearer: BearerAuth,
-) -> impl Responder {
-    println!("DEBUG: Petición de transferencia recibida.");
-
-    let claims = match decode::<Claims>(
-        bearer.token(),
-        &DecodingKey::from_secret(env::var("JWT_SECRET").expect("JWT_SECRET must be set").as_ref()),
-        &Validation::new(jsonwebtoken::Algorithm::default()),
-    ) {
-        Ok(token_data) => token_data.claims,
-        Err(e) => {
-            println!("ERROR: Fallo al decodificar el token JWT: {:?}", e);
-            return HttpResponse::Unauthorized().finish();
-        }
-    };
-
-    println!("DEBUG: Token JWT decodificado con éxito para el usuario ID: {}", claims.sub);
-
-    if transaction_data.amount <= 0.0 {
-        println!("ERROR: Monto de transferencia no válido: {}", transaction_data.amount);
-        return HttpResponse::BadRequest().json("La cantidad debe ser mayor a 0");
-    }
-
-    println!("DEBUG: Llamando al servicio de procesamiento de transferencia.");
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let idempotency_key = idempotency::idempotency_key(&req, "transfer", &*transaction_data)?;
 
     // Llama al servicio para procesar la lógica de negocio
-    match transaction_service::process_transfer(&pool.db, claims.sub, &transaction_data).await {
-        Ok(_) => {
-            println!("DEBUG: El servicio de transferencia se completó con éxito.");
-            HttpResponse::Ok().json(serde_json::json!({
-                "message": "Transferencia realizada con éxito"
-            }))
-        },
-        Err(e) => {
-            println!("ERROR: El servicio de transferencia falló.");
-            e
-        },
-    }
+    let result = transaction_service::process_transfer(
+        pool.store.as_ref(),
+        &pool.config.limits,
+        user.user_id,
+        &transaction_data,
+        idempotency_key.as_ref(),
+    )
+    .await;
+    pool.metrics.record_money_operation(MoneyOperation::Transfer, transaction_data.amount, &result);
+    let response = result?;
+
+    Ok(idempotency::into_response(response))
 }
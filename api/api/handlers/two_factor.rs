@@ -0,0 +1,86 @@
+// src/api/handlers/two_factor.rs
+
+use crate::errors::{BankError, ProblemDetails};
+use crate::middleware::rbac::AuthenticatedUser;
+use crate::models::{RecoveryCodes, TwoFactorCode, TwoFactorDisable};
+use crate::services::two_factor_service::{self, TwoFactorSetup};
+use actix_web::{post, web, HttpRequest, HttpResponse};
+
+use super::login::client_ip;
+
+// Sin `RequirePermission`: quien tiene el alta pendiente tiene que poder completarla
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "2fa",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Secreto TOTP pendiente de confirmar con /protected/2fa/enable", body = TwoFactorSetup),
+        (status = 400, description = "INVALID_REQUEST: ya está activada", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/2fa/setup")]
+pub async fn setup(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, BankError> {
+    let setup = two_factor_service::setup(pool.store.as_ref(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(setup))
+}
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "2fa",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "2FA activada; los códigos de recuperación solo se muestran ahora", body = RecoveryCodes),
+        (status = 400, description = "INVALID_REQUEST: no hay un alta pendiente", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "INVALID_TWO_FACTOR_CODE", body = ProblemDetails, content_type = "application/problem+json"),
+    ),
+)]
+#[post("/2fa/enable")]
+pub async fn enable(
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    data: web::Json<TwoFactorCode>,
+) -> Result<HttpResponse, BankError> {
+    let recovery_codes = two_factor_service::enable(pool.store.as_ref(), user.user_id, &data.code).await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodes {
+        message: "Verificación en dos pasos activada. Guarda los códigos de recuperación.".to_string(),
+        recovery_codes,
+    }))
+}
+
+#[utoipa::path(
+    context_path = "/protected",
+    tag = "2fa",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "2FA desactivada"),
+        (status = 400, description = "INVALID_REQUEST: no está activada o el rol la exige", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "INVALID_CREDENTIALS o INVALID_TWO_FACTOR_CODE", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "TOO_MANY_ATTEMPTS: usuario o IP bloqueados temporalmente", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = i64, description = "Segundos hasta el siguiente intento"))),
+    ),
+)]
+#[post("/2fa/disable")]
+pub async fn disable(
+    req: HttpRequest,
+    pool: web::Data<crate::AppState>,
+    user: AuthenticatedUser,
+    data: web::Json<TwoFactorDisable>,
+) -> Result<HttpResponse, BankError> {
+    let client_ip = client_ip(&req, &pool.config.server.trusted_proxies);
+    two_factor_service::disable(
+        pool.store.as_ref(),
+        &pool.config.limits,
+        user.user_id,
+        &data.password,
+        &data.code,
+        client_ip.as_deref(),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,41 @@
+// src/api/idempotency.rs
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::errors::BankError;
+use crate::services::idempotency::{IdempotencyKey, StoredResponse};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+// Lee la cabecera `Idempotency-Key`, si viene, y calcula la huella de la petición
+pub fn idempotency_key<T: Serialize>(
+    req: &HttpRequest,
+    endpoint: &str,
+    body: &T,
+) -> Result<Option<IdempotencyKey>, BankError> {
+    let header = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(h) => h,
+        None => return Ok(None),
+    };
+
+    let key = header
+        .to_str()
+        .map_err(|_| BankError::InvalidRequest("La Idempotency-Key no es válida.".to_string()))?;
+
+    IdempotencyKey::new(key, endpoint, body).map(Some)
+}
+
+pub fn into_response(stored: StoredResponse) -> HttpResponse {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut builder = HttpResponse::build(status);
+    if stored.replayed {
+        builder.insert_header(("Idempotent-Replayed", "true"));
+    }
+    // Un rechazo repetido conserva el tipo de contenido de los errores
+    if status.is_client_error() {
+        builder.content_type("application/problem+json");
+    }
+    builder.json(stored.body)
+}
@@ -1,2 +1,4 @@
 pub mod handlers;
+pub mod idempotency;
+pub mod openapi;
 pub mod routes;
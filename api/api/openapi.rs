@@ -0,0 +1,130 @@
+// src/api/openapi.rs
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Ref, RefOr, Response, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::api::handlers::{self, accountant};
+use crate::errors::ProblemDetails;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+// Documento OpenAPI 3 de toda la API, generado a partir de los atributos
+// `#[utoipa::path]` de los handlers y de los modelos con `ToSchema`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "FriendBank API",
+        description = "Cuentas, transferencias y operaciones de caja de FriendBank. Los errores \
+                       se devuelven como `application/problem+json` (RFC 7807); los clientes deben \
+                       usar el campo `code`."
+    ),
+    paths(
+        handlers::login::login,
+        handlers::login::login_two_factor,
+        handlers::register::register,
+        handlers::password::forgot_password,
+        handlers::password::reset_password,
+        handlers::session::refresh,
+        handlers::session::logout,
+        handlers::account::balance,
+        handlers::account::get_account,
+        handlers::history::transactions,
+        handlers::transaction::transfer,
+        handlers::password::change_password,
+        handlers::invites::create_invite,
+        handlers::invites::list_invites,
+        handlers::two_factor::setup,
+        handlers::two_factor::enable,
+        handlers::two_factor::disable,
+        handlers::session::list_sessions,
+        handlers::session::terminate_session,
+        handlers::session::terminate_all_sessions,
+        handlers::signup::signup,
+        accountant::deposit::deposit,
+        accountant::withdraw::withdraw,
+        accountant::ledger::check,
+        accountant::auth_events::list,
+        handlers::admin::list_users,
+        handlers::admin::create_accountant,
+        handlers::admin::change_role,
+        handlers::admin::disable_user,
+        handlers::admin::enable_user,
+        handlers::admin::reset_credentials,
+        handlers::admin::set_two_factor_policy,
+        handlers::health::healthz,
+        handlers::health::readyz,
+        handlers::jwks::jwks,
+        handlers::metrics::metrics,
+    ),
+    components(schemas(ProblemDetails)),
+    modifiers(&CommonResponses),
+    tags(
+        (name = "auth", description = "Login, refresh tokens y recuperación de la contraseña"),
+        (name = "registro", description = "Alta de usuarios con código de invitación"),
+        (name = "cuenta", description = "Saldo, movimientos y transferencias del usuario autenticado"),
+        (name = "sesiones", description = "Sesiones abiertas del usuario"),
+        (name = "2fa", description = "Verificación en dos pasos con TOTP"),
+        (name = "contador", description = "Depósitos, retiros y auditoría"),
+        (name = "admin", description = "Gestión de usuarios y roles"),
+        (name = "sistema", description = "Sondas, métricas y claves públicas"),
+    )
+)]
+pub struct ApiDoc;
+
+// El esquema de autenticación y las respuestas de error que comparten todas las
+// operaciones, para no repetirlas en cada handler
+struct CommonResponses;
+
+impl Modify for CommonResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.security_schemes.insert(
+            "bearer_auth".to_string(),
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        for (name, description) in [
+            ("InvalidRequest", "INVALID_REQUEST: el cuerpo o los parámetros no son válidos"),
+            ("Unauthorized", "UNAUTHORIZED: falta el access token, no es válido o la sesión está cerrada"),
+            ("Forbidden", "FORBIDDEN, USER_DISABLED o TWO_FACTOR_ENROLLMENT_REQUIRED"),
+            ("InternalError", "INTERNAL_ERROR"),
+        ] {
+            components.responses.insert(name.to_string(), RefOr::T(problem_response(description)));
+        }
+
+        // Las respuestas propias de cada handler tienen prioridad sobre estas
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [&mut item.get, &mut item.post, &mut item.put, &mut item.delete];
+            for operation in operations.into_iter().flatten() {
+                let mut common = vec![("500", "InternalError")];
+                if operation.request_body.is_some() {
+                    common.push(("400", "InvalidRequest"));
+                }
+                if operation.security.is_some() {
+                    common.push(("401", "Unauthorized"));
+                    common.push(("403", "Forbidden"));
+                }
+
+                for (status, name) in common {
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.to_string())
+                        .or_insert_with(|| RefOr::Ref(Ref::from_response_name(name)));
+                }
+            }
+        }
+    }
+}
+
+fn problem_response(description: &str) -> Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            PROBLEM_CONTENT_TYPE,
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("ProblemDetails")))
+                .build(),
+        )
+        .build()
+}
@@ -1,26 +1,80 @@
 use crate::api::handlers::{self, accountant};
 use crate::middleware;
+use crate::middleware::rbac::RequirePermission;
+use crate::models::Permission;
 use actix_web::web;
 use actix_web_httpauth::middleware::HttpAuthentication;
 
 pub fn config_routes(cfg: &mut web::ServiceConfig) {
+    // Claves públicas para que otros servicios verifiquen nuestros tokens
+    cfg.service(handlers::jwks::jwks);
+
+    // Sondas del orquestador, sin autenticación
+    cfg.service(handlers::health::healthz)
+        .service(handlers::health::readyz);
+
+    // Métricas para Prometheus
+    cfg.service(handlers::metrics::metrics);
+
+    // Especificación OpenAPI y, fuera de producción, Swagger UI
+    cfg.service(handlers::docs::openapi)
+        .service(handlers::docs::docs)
+        .service(handlers::docs::docs_asset);
+
     // Rutas que no necesitan autenticación
     cfg.service(web::scope("/auth")
         .service(handlers::login::login)
+        .service(handlers::login::login_two_factor)
+        .service(handlers::register::register)
+        .service(handlers::password::forgot_password)
+        .service(handlers::password::reset_password)
+        .service(handlers::session::refresh)
+        .service(handlers::session::logout)
     );
 
+    // Autentica y carga los permisos del rol. `/accountant` y `/admin` exigen además un
+    // permiso para todo el scope; un handler que necesite otro más concreto lo declara con
+    // `RequirePermission` en su atributo de ruta, que corre dentro de estos middlewares.
     let auth_middleware = HttpAuthentication::bearer(middleware::jwt_auth::jwt_auth_middleware);
 
     cfg.service(web::scope("/protected")
         .wrap(auth_middleware.clone())
         .service(handlers::signup::signup)
         .service(handlers::transaction::transfer)
+        .service(handlers::account::balance)
+        .service(handlers::account::get_account)
+        .service(handlers::history::transactions)
+        .service(handlers::invites::create_invite)
+        .service(handlers::invites::list_invites)
+        .service(handlers::password::change_password)
+        .service(handlers::two_factor::setup)
+        .service(handlers::two_factor::enable)
+        .service(handlers::two_factor::disable)
+        .service(handlers::session::list_sessions)
+        .service(handlers::session::terminate_session)
+        .service(handlers::session::terminate_all_sessions)
     );
  
-    // Rutas solo para el usuario "contador"
+    // Rutas del contador; los administradores también revisan el libro y los eventos de login
     cfg.service(web::scope("/accountant")
-        .wrap(auth_middleware) // <-- ¡Correcto!
+        .wrap(RequirePermission::new(Permission::AuditRead))
+        .wrap(auth_middleware.clone())
         .service(accountant::deposit::deposit)
         .service(accountant::withdraw::withdraw)
+        .service(accountant::ledger::check)
+        .service(accountant::auth_events::list)
+    );
+
+    // Gestión de usuarios, solo para administradores
+    cfg.service(web::scope("/admin")
+        .wrap(RequirePermission::new(Permission::UsersManage))
+        .wrap(auth_middleware)
+        .service(handlers::admin::list_users)
+        .service(handlers::admin::create_accountant)
+        .service(handlers::admin::change_role)
+        .service(handlers::admin::disable_user)
+        .service(handlers::admin::enable_user)
+        .service(handlers::admin::reset_credentials)
+        .service(handlers::admin::set_two_factor_policy)
     );
 }
@@ -0,0 +1,572 @@
+// src/config.rs
+
+use std::env;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+// Fichero que se lee si existe y no se indica otro con CONFIG_FILE
+const DEFAULT_CONFIG_FILE: &str = "friendbank.toml";
+
+// Configuración de la aplicación. Se carga una sola vez al arrancar: primero los valores por
+// defecto, encima el fichero TOML (si lo hay) y encima las variables de entorno.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub registration: RegistrationConfig,
+    pub notifier: NotifierConfig,
+    pub logging: LoggingConfig,
+    pub limits: LimitsConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    // `None` deja que actix use un worker por núcleo
+    pub workers: Option<usize>,
+    pub keep_alive_seconds: u64,
+    // Tiempo máximo para recibir las cabeceras de una petición
+    pub client_request_timeout_seconds: u64,
+    pub shutdown_timeout_seconds: u64,
+    // Fuera de producción se publica la documentación interactiva en /docs
+    pub environment: Environment,
+    // Proxies propios (balanceador, CDN...). Solo a ellos se les cree la IP del cliente que
+    // envían en X-Forwarded-For; vacío, la IP es la de la conexión
+    pub trusted_proxies: Vec<IpAddr>,
+    // Token Bearer que debe enviar quien lea /metrics. Sin él, en producción no se publican
+    pub metrics_token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: "127.0.0.1:8080".to_string(),
+            workers: None,
+            keep_alive_seconds: 5,
+            client_request_timeout_seconds: 5,
+            shutdown_timeout_seconds: 30,
+            environment: Environment::Production,
+            trusted_proxies: Vec::new(),
+            metrics_token: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Development,
+    #[default]
+    Production,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    // Cuánto se espera por una conexión libre antes de fallar la petición
+    pub acquire_timeout_seconds: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // Secreto HS256; con claves asimétricas solo sirve para aceptar los tokens antiguos
+    pub jwt_secret: Option<String>,
+    // Directorio con `<kid>.pub.pem` y `<kid>.key.pem` (ver `services::jwt_keys`)
+    pub jwt_keys_dir: Option<String>,
+    pub jwt_active_kid: Option<String>,
+    pub access_token_ttl_minutes: i64,
+    pub refresh_token_ttl_days: i64,
+    pub password_reset_ttl_minutes: i64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            jwt_secret: None,
+            jwt_keys_dir: None,
+            jwt_active_kid: None,
+            access_token_ttl_minutes: 120,
+            refresh_token_ttl_days: 30,
+            password_reset_ttl_minutes: 30,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegistrationConfig {
+    pub require_invite_code: bool,
+    // Invitaciones sin usar ni caducar que puede tener cada usuario
+    pub max_active_invites: usize,
+    pub invite_code_ttl_days: i64,
+}
+
+impl Default for RegistrationConfig {
+    fn default() -> Self {
+        RegistrationConfig {
+            require_invite_code: true,
+            max_active_invites: 5,
+            invite_code_ttl_days: 7,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierKind {
+    // `log` y `file` solo sirven en desarrollo; producción tiene que elegir `webhook`
+    #[default]
+    Log,
+    File,
+    Webhook,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifierConfig {
+    pub kind: NotifierKind,
+    pub file: String,
+    pub webhook_url: Option<String>,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        NotifierConfig { kind: NotifierKind::Log, file: "notifications.log".to_string(), webhook_url: None }
+    }
+}
+
+// Límites de negocio: montos, paginación y bloqueo del login
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    // Montos aceptados en transferencias, depósitos y retiros
+    pub max_amount: Decimal,
+    pub max_amount_scale: u32,
+    // Movimientos por página del historial cuando no se pide otro número, y el máximo
+    pub default_page_size: u32,
+    pub max_page_size: u32,
+    pub username_throttle: ThrottlePolicy,
+    // Una IP puede ser una oficina entera detrás de un NAT, así que es más tolerante
+    pub ip_throttle: ThrottlePolicy,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_amount: dec!(1_000_000_000),
+            max_amount_scale: 2,
+            default_page_size: 20,
+            max_page_size: 100,
+            username_throttle: ThrottlePolicy {
+                free_failures: 3,
+                base_delay_seconds: 2,
+                max_delay_seconds: 120,
+                lockout_failures: 10,
+                lockout_minutes: 15,
+            },
+            ip_throttle: ThrottlePolicy {
+                free_failures: 10,
+                base_delay_seconds: 1,
+                max_delay_seconds: 60,
+                lockout_failures: 50,
+                lockout_minutes: 30,
+            },
+        }
+    }
+}
+
+// Tras `free_failures` fallos cada intento espera el doble que el anterior, hasta
+// `max_delay_seconds`; al llegar a `lockout_failures` el sujeto queda bloqueado
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ThrottlePolicy {
+    pub free_failures: i32,
+    pub base_delay_seconds: i64,
+    pub max_delay_seconds: i64,
+    pub lockout_failures: i32,
+    pub lockout_minutes: i64,
+}
+
+impl ThrottlePolicy {
+    fn problems(&self, name: &str, problems: &mut Vec<String>) {
+        if self.free_failures < 0 {
+            problems.push(format!("limits.{}.free_failures no puede ser negativo", name));
+        }
+        if self.base_delay_seconds <= 0 || self.max_delay_seconds < self.base_delay_seconds {
+            problems.push(format!("limits.{}: se necesita 0 < base_delay_seconds <= max_delay_seconds", name));
+        }
+        if self.lockout_failures <= self.free_failures {
+            problems.push(format!("limits.{}.lockout_failures debe ser mayor que free_failures", name));
+        }
+        if self.lockout_minutes <= 0 {
+            problems.push(format!("limits.{}.lockout_minutes debe ser mayor que 0", name));
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // Directivas de `tracing_subscriber::EnvFilter`, p. ej. "info,sqlx=warn"
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { level: "info,sqlx=warn".to_string(), format: LogFormat::Text }
+    }
+}
+
+impl Config {
+    // CONFIG_FILE (o friendbank.toml, si existe) más las variables de entorno, ya validado
+    pub fn load() -> Result<Config, String> {
+        let mut config = match env::var("CONFIG_FILE") {
+            Ok(path) => Config::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => Config::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            Err(_) => Config::default(),
+        };
+
+        config.apply_env(|name| env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Config::from_toml(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Config, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    // Las variables de entorno tienen prioridad sobre el fichero
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        let server = &mut self.server;
+        set_from(&lookup, "BIND_ADDRESS", &mut server.bind_address)?;
+        set_optional_from(&lookup, "SERVER_WORKERS", &mut server.workers)?;
+        set_from(&lookup, "KEEP_ALIVE_SECONDS", &mut server.keep_alive_seconds)?;
+        set_from(&lookup, "CLIENT_REQUEST_TIMEOUT_SECONDS", &mut server.client_request_timeout_seconds)?;
+        set_from(&lookup, "SHUTDOWN_TIMEOUT_SECONDS", &mut server.shutdown_timeout_seconds)?;
+        if let Some(environment) = lookup("APP_ENV") {
+            server.environment = match environment.as_str() {
+                "development" => Environment::Development,
+                "production" => Environment::Production,
+                _ => return Err(format!("APP_ENV: valor desconocido '{}'", environment)),
+            };
+        }
+        if let Some(proxies) = lookup("TRUSTED_PROXIES") {
+            server.trusted_proxies = proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .map(|proxy| proxy.parse().map_err(|_| format!("TRUSTED_PROXIES: '{}' no es una IP", proxy)))
+                .collect::<Result<_, _>>()?;
+        }
+        set_optional_from(&lookup, "METRICS_TOKEN", &mut server.metrics_token)?;
+
+        let database = &mut self.database;
+        set_from(&lookup, "DATABASE_URL", &mut database.url)?;
+        set_from(&lookup, "DATABASE_MAX_CONNECTIONS", &mut database.max_connections)?;
+        set_from(&lookup, "DATABASE_MIN_CONNECTIONS", &mut database.min_connections)?;
+        set_from(&lookup, "DATABASE_ACQUIRE_TIMEOUT_SECONDS", &mut database.acquire_timeout_seconds)?;
+
+        let auth = &mut self.auth;
+        set_optional_from(&lookup, "JWT_SECRET", &mut auth.jwt_secret)?;
+        set_optional_from(&lookup, "JWT_KEYS_DIR", &mut auth.jwt_keys_dir)?;
+        set_optional_from(&lookup, "JWT_ACTIVE_KID", &mut auth.jwt_active_kid)?;
+        set_from(&lookup, "ACCESS_TOKEN_TTL_MINUTES", &mut auth.access_token_ttl_minutes)?;
+        set_from(&lookup, "REFRESH_TOKEN_TTL_DAYS", &mut auth.refresh_token_ttl_days)?;
+        set_from(&lookup, "PASSWORD_RESET_TTL_MINUTES", &mut auth.password_reset_ttl_minutes)?;
+
+        let registration = &mut self.registration;
+        set_from(&lookup, "REQUIRE_INVITE_CODE", &mut registration.require_invite_code)?;
+        set_from(&lookup, "MAX_ACTIVE_INVITES", &mut registration.max_active_invites)?;
+        set_from(&lookup, "INVITE_CODE_TTL_DAYS", &mut registration.invite_code_ttl_days)?;
+
+        if let Some(kind) = lookup("NOTIFIER") {
+            self.notifier.kind = match kind.as_str() {
+                "log" => NotifierKind::Log,
+                "file" => NotifierKind::File,
+                "webhook" => NotifierKind::Webhook,
+                _ => return Err(format!("NOTIFIER: valor desconocido '{}'", kind)),
+            };
+        }
+        set_from(&lookup, "NOTIFIER_FILE", &mut self.notifier.file)?;
+        set_optional_from(&lookup, "NOTIFIER_WEBHOOK_URL", &mut self.notifier.webhook_url)?;
+
+        let limits = &mut self.limits;
+        set_from(&lookup, "MAX_AMOUNT", &mut limits.max_amount)?;
+        set_from(&lookup, "MAX_AMOUNT_SCALE", &mut limits.max_amount_scale)?;
+        set_from(&lookup, "DEFAULT_PAGE_SIZE", &mut limits.default_page_size)?;
+        set_from(&lookup, "MAX_PAGE_SIZE", &mut limits.max_page_size)?;
+
+        set_from(&lookup, "LOG_LEVEL", &mut self.logging.level)?;
+        if let Some(format) = lookup("LOG_FORMAT") {
+            self.logging.format = match format.as_str() {
+                "text" => LogFormat::Text,
+                "json" => LogFormat::Json,
+                _ => return Err(format!("LOG_FORMAT: valor desconocido '{}'", format)),
+            };
+        }
+
+        Ok(())
+    }
+
+    // Rechaza al arrancar lo que de otro modo fallaría en mitad de una petición
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        if self.server.bind_address.parse::<std::net::SocketAddr>().is_err() {
+            problems.push(format!("server.bind_address '{}' no es una dirección válida", self.server.bind_address));
+        }
+        if self.server.workers == Some(0) {
+            problems.push("server.workers debe ser mayor que 0".to_string());
+        }
+        if self.server.metrics_token.as_deref().is_some_and(|token| token.len() < 32) {
+            problems.push("server.metrics_token (METRICS_TOKEN) debe tener al menos 32 caracteres".to_string());
+        }
+        if self.database.url.is_empty() {
+            problems.push("database.url (DATABASE_URL) es obligatorio".to_string());
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections debe ser mayor que 0".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push("database.min_connections no puede superar max_connections".to_string());
+        }
+
+        let auth = &self.auth;
+        match (&auth.jwt_keys_dir, &auth.jwt_active_kid) {
+            (Some(_), None) => problems.push("auth.jwt_active_kid es obligatorio con jwt_keys_dir".to_string()),
+            (None, _) if auth.jwt_secret.as_deref().unwrap_or_default().is_empty() => {
+                problems.push("auth.jwt_secret (JWT_SECRET) o auth.jwt_keys_dir son obligatorios".to_string())
+            }
+            _ => {}
+        }
+        if auth.access_token_ttl_minutes <= 0 {
+            problems.push("auth.access_token_ttl_minutes debe ser mayor que 0".to_string());
+        }
+        if auth.refresh_token_ttl_days <= 0 {
+            problems.push("auth.refresh_token_ttl_days debe ser mayor que 0".to_string());
+        }
+        if auth.password_reset_ttl_minutes <= 0 {
+            problems.push("auth.password_reset_ttl_minutes debe ser mayor que 0".to_string());
+        }
+        if self.registration.invite_code_ttl_days <= 0 {
+            problems.push("registration.invite_code_ttl_days debe ser mayor que 0".to_string());
+        }
+
+        // El notificador de desarrollo nunca entrega el código y el fichero lo deja en disco
+        let notifier = &self.notifier;
+        if self.server.environment == Environment::Production && notifier.kind != NotifierKind::Webhook {
+            problems.push("notifier.kind (NOTIFIER) debe ser 'webhook' en producción".to_string());
+        }
+        if notifier.kind == NotifierKind::Webhook {
+            match notifier.webhook_url.as_deref().map(reqwest::Url::parse) {
+                Some(Ok(url)) if url.scheme() == "https" || url.scheme() == "http" => {}
+                Some(_) => problems.push("notifier.webhook_url no es una URL http(s) válida".to_string()),
+                None => {
+                    problems.push("notifier.webhook_url (NOTIFIER_WEBHOOK_URL) es obligatorio con 'webhook'".to_string())
+                }
+            }
+        }
+        // Los montos se guardan como DECIMAL(19, 2)
+        let limits = &self.limits;
+        if limits.max_amount <= Decimal::ZERO || limits.max_amount >= dec!(100_000_000_000_000_000) {
+            problems.push("limits.max_amount debe ser mayor que 0 y menor que 10^17".to_string());
+        }
+        if limits.max_amount_scale > 2 {
+            problems.push("limits.max_amount_scale no puede ser mayor que 2".to_string());
+        }
+        if limits.default_page_size == 0 || limits.default_page_size > limits.max_page_size {
+            problems.push("limits: se necesita 0 < default_page_size <= max_page_size".to_string());
+        }
+        limits.username_throttle.problems("username_throttle", &mut problems);
+        limits.ip_throttle.problems("ip_throttle", &mut problems);
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            problems.push(format!("logging.level '{}' no es válido: {}", self.logging.level, e));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+}
+
+fn set_from<T: std::str::FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    target: &mut T,
+) -> Result<(), String> {
+    if let Some(value) = lookup(name) {
+        *target = value.parse().map_err(|_| format!("{}: valor inválido '{}'", name, value))?;
+    }
+    Ok(())
+}
+
+fn set_optional_from<T: std::str::FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    target: &mut Option<T>,
+) -> Result<(), String> {
+    if let Some(value) = lookup(name) {
+        *target = Some(value.parse().map_err(|_| format!("{}: valor inválido '{}'", name, value))?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn environment_overrides_the_file_and_the_file_overrides_the_defaults() {
+        let mut config = Config::from_toml(
+            r#"
+            [server]
+            bind_address = "0.0.0.0:9000"
+
+            [database]
+            url = "mysql://fichero/friendbank"
+            max_connections = 20
+
+            [auth]
+            jwt_secret = "secreto"
+            access_token_ttl_minutes = 15
+
+            [notifier]
+            kind = "webhook"
+            webhook_url = "https://avisos.example/friendbank"
+
+            [limits]
+            max_amount = "5000"
+            default_page_size = 10
+
+            [limits.username_throttle]
+            free_failures = 2
+            base_delay_seconds = 1
+            max_delay_seconds = 30
+            lockout_failures = 5
+            lockout_minutes = 10
+            "#,
+        )
+        .unwrap();
+
+        config
+            .apply_env(lookup(&[
+                ("DATABASE_MAX_CONNECTIONS", "8"),
+                ("DEFAULT_PAGE_SIZE", "50"),
+                ("REQUIRE_INVITE_CODE", "false"),
+                ("TRUSTED_PROXIES", "10.0.0.1, ::1"),
+                ("METRICS_TOKEN", "prometheus-scraper-0123456789abcdef"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.server.bind_address, "0.0.0.0:9000");
+        assert_eq!(config.database.url, "mysql://fichero/friendbank");
+        assert_eq!(config.database.max_connections, 8);
+        assert_eq!(config.auth.access_token_ttl_minutes, 15);
+        assert_eq!(config.auth.refresh_token_ttl_days, 30);
+        assert!(!config.registration.require_invite_code);
+        assert_eq!(config.limits.max_amount, dec!(5000));
+        assert_eq!(config.limits.default_page_size, 50);
+        assert_eq!(config.limits.username_throttle.lockout_failures, 5);
+        assert_eq!(config.limits.ip_throttle, LimitsConfig::default().ip_throttle);
+        assert_eq!(config.server.trusted_proxies, ["10.0.0.1".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]);
+        assert_eq!(config.server.metrics_token.as_deref(), Some("prometheus-scraper-0123456789abcdef"));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_at_boot() {
+        assert!(Config::from_toml("[server]\nbind_adress = \"0.0.0.0:80\"").is_err());
+
+        let mut config = Config::default();
+        assert!(config.apply_env(lookup(&[("DATABASE_MAX_CONNECTIONS", "muchas")])).is_err());
+        assert!(config.apply_env(lookup(&[("APP_ENV", "staging")])).is_err());
+        assert!(config.apply_env(lookup(&[("TRUSTED_PROXIES", "10.0.0.1,proxy.interno")])).is_err());
+
+        assert!(config.apply_env(lookup(&[("MAX_AMOUNT", "mil")])).is_err());
+
+        let problems = Config::default().validate().unwrap_err();
+        assert!(problems.contains("DATABASE_URL"));
+        assert!(problems.contains("JWT_SECRET"));
+        assert!(!problems.contains("limits"));
+
+        let mut config = Config::default();
+        config.limits.max_amount = dec!(0);
+        config.limits.max_amount_scale = 3;
+        config.limits.default_page_size = 200;
+        config.limits.ip_throttle.lockout_failures = 0;
+        config.server.metrics_token = Some("corto".to_string());
+        let problems = config.validate().unwrap_err();
+        assert!(problems.contains("limits.max_amount "));
+        assert!(problems.contains("limits.max_amount_scale"));
+        assert!(problems.contains("default_page_size"));
+        assert!(problems.contains("limits.ip_throttle.lockout_failures"));
+        assert!(problems.contains("METRICS_TOKEN"));
+    }
+
+    #[test]
+    fn production_requires_the_webhook_notifier() {
+        let mut config = Config::default();
+        config.database.url = "mysql://localhost/friendbank".to_string();
+        config.auth.jwt_secret = Some("secreto".to_string());
+
+        assert!(config.validate().unwrap_err().contains("NOTIFIER"));
+
+        config.apply_env(lookup(&[("NOTIFIER", "webhook")])).unwrap();
+        assert!(config.validate().unwrap_err().contains("NOTIFIER_WEBHOOK_URL"));
+
+        config.apply_env(lookup(&[("NOTIFIER_WEBHOOK_URL", "https://avisos.example/friendbank")])).unwrap();
+        assert!(config.validate().is_ok());
+
+        // En desarrollo basta con el log
+        config.apply_env(lookup(&[("APP_ENV", "development"), ("NOTIFIER", "log")])).unwrap();
+        assert!(config.validate().is_ok());
+    }
+}
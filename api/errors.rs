@@ -0,0 +1,227 @@
+// src/errors.rs
+
+use std::fmt;
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Errores de dominio del banco. Los servicios devuelven este tipo y solo la capa
+// HTTP lo convierte en una respuesta `application/problem+json` (RFC 7807).
+#[derive(Debug)]
+pub enum BankError {
+    InvalidRequest(String),
+    InvalidAmount(String),
+    InsufficientFunds,
+    UnknownRecipient,
+    UnknownUser,
+    AccountNotFound,
+    SessionNotFound,
+    SelfTransfer,
+    UsernameTaken,
+    WeakPassword(String),
+    InvalidInviteCode,
+    InvalidResetToken,
+    InvalidCredentials,
+    InvalidTwoFactorCode,
+    TwoFactorEnrollmentRequired,
+    Unauthorized,
+    Forbidden,
+    UserDisabled,
+    // Segundos que el cliente debe esperar antes de reintentar
+    TooManyAttempts(i64),
+    IdempotencyKeyReused,
+    Database(sqlx::Error),
+    Internal(String),
+}
+
+// Cuerpo de todas las respuestas de error
+#[derive(Serialize, ToSchema)]
+#[schema(example = json!({
+    "type": "urn:friendbank:problem:insufficient-funds",
+    "title": "Fondos insuficientes",
+    "status": 400,
+    "detail": "La cuenta no tiene fondos suficientes.",
+    "code": "INSUFFICIENT_FUNDS"
+}))]
+pub struct ProblemDetails<'a> {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'a str,
+    status: u16,
+    detail: String,
+    code: &'a str,
+}
+
+impl ProblemDetails<'_> {
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+}
+
+impl BankError {
+    // Código estable y legible por máquinas; los clientes deben usar este campo
+    pub fn code(&self) -> &'static str {
+        match self {
+            BankError::InvalidRequest(_) => "INVALID_REQUEST",
+            BankError::InvalidAmount(_) => "INVALID_AMOUNT",
+            BankError::InsufficientFunds => "INSUFFICIENT_FUNDS",
+            BankError::UnknownRecipient => "UNKNOWN_RECIPIENT",
+            BankError::UnknownUser => "UNKNOWN_USER",
+            BankError::AccountNotFound => "ACCOUNT_NOT_FOUND",
+            BankError::SessionNotFound => "SESSION_NOT_FOUND",
+            BankError::SelfTransfer => "SELF_TRANSFER",
+            BankError::UsernameTaken => "USERNAME_TAKEN",
+            BankError::WeakPassword(_) => "WEAK_PASSWORD",
+            BankError::InvalidInviteCode => "INVALID_INVITE_CODE",
+            BankError::InvalidResetToken => "INVALID_RESET_TOKEN",
+            BankError::InvalidCredentials => "INVALID_CREDENTIALS",
+            BankError::InvalidTwoFactorCode => "INVALID_TWO_FACTOR_CODE",
+            BankError::TwoFactorEnrollmentRequired => "TWO_FACTOR_ENROLLMENT_REQUIRED",
+            BankError::Unauthorized => "UNAUTHORIZED",
+            BankError::Forbidden => "FORBIDDEN",
+            BankError::UserDisabled => "USER_DISABLED",
+            BankError::TooManyAttempts(_) => "TOO_MANY_ATTEMPTS",
+            BankError::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            BankError::Database(_) | BankError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    // Cuerpo de la respuesta; también se guarda para repetir un rechazo por Idempotency-Key
+    pub fn problem_details(&self) -> ProblemDetails<'static> {
+        ProblemDetails {
+            problem_type: format!("urn:friendbank:problem:{}", self.code().to_lowercase().replace('_', "-")),
+            title: self.title(),
+            status: self.status_code().as_u16(),
+            detail: self.detail(),
+            code: self.code(),
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            BankError::InvalidRequest(_) => "Petición inválida",
+            BankError::InvalidAmount(_) => "Monto inválido",
+            BankError::InsufficientFunds => "Fondos insuficientes",
+            BankError::UnknownRecipient => "Receptor desconocido",
+            BankError::UnknownUser => "Usuario desconocido",
+            BankError::AccountNotFound => "Cuenta no encontrada",
+            BankError::SessionNotFound => "Sesión no encontrada",
+            BankError::SelfTransfer => "Transferencia a uno mismo",
+            BankError::UsernameTaken => "Nombre de usuario ocupado",
+            BankError::WeakPassword(_) => "Contraseña débil",
+            BankError::InvalidInviteCode => "Código de invitación inválido",
+            BankError::InvalidResetToken => "Token de restablecimiento inválido",
+            BankError::InvalidCredentials => "Credenciales incorrectas",
+            BankError::InvalidTwoFactorCode => "Código de verificación incorrecto",
+            BankError::TwoFactorEnrollmentRequired => "Verificación en dos pasos obligatoria",
+            BankError::Unauthorized => "No autorizado",
+            BankError::Forbidden => "Acceso denegado",
+            BankError::UserDisabled => "Usuario deshabilitado",
+            BankError::TooManyAttempts(_) => "Demasiados intentos",
+            BankError::IdempotencyKeyReused => "Idempotency-Key reutilizada",
+            BankError::Database(_) | BankError::Internal(_) => "Error interno",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            BankError::InvalidRequest(message)
+            | BankError::InvalidAmount(message)
+            | BankError::WeakPassword(message) => message.clone(),
+            BankError::InsufficientFunds => "La cuenta no tiene fondos suficientes.".to_string(),
+            BankError::UnknownRecipient => "El usuario receptor no existe.".to_string(),
+            BankError::UnknownUser => "El usuario no existe.".to_string(),
+            BankError::AccountNotFound => "No se encontró la cuenta.".to_string(),
+            BankError::SessionNotFound => "No se encontró la sesión.".to_string(),
+            BankError::SelfTransfer => "No puedes transferirte a ti mismo.".to_string(),
+            BankError::UsernameTaken => "Ese nombre de usuario ya está registrado.".to_string(),
+            BankError::InvalidInviteCode => {
+                "El código de invitación no existe, ya se usó o ha caducado.".to_string()
+            }
+            BankError::InvalidResetToken => {
+                "El token de restablecimiento no existe, ya se usó o ha caducado.".to_string()
+            }
+            BankError::InvalidCredentials => "Usuario o contraseña incorrectos.".to_string(),
+            BankError::InvalidTwoFactorCode => {
+                "El código de verificación no es válido o el desafío ha caducado.".to_string()
+            }
+            BankError::TwoFactorEnrollmentRequired => {
+                "Tu rol exige la verificación en dos pasos; actívala en /protected/2fa.".to_string()
+            }
+            BankError::Unauthorized => "Acceso no autorizado.".to_string(),
+            BankError::Forbidden => "No tienes permiso para realizar esta acción.".to_string(),
+            BankError::UserDisabled => "El usuario está deshabilitado.".to_string(),
+            BankError::TooManyAttempts(retry_after) => {
+                format!("Demasiados intentos fallidos; vuelve a intentarlo en {} segundos.", retry_after)
+            }
+            BankError::IdempotencyKeyReused => {
+                "La Idempotency-Key ya se usó con una petición diferente.".to_string()
+            }
+            // Nunca se exponen detalles internos al cliente
+            BankError::Database(_) | BankError::Internal(_) => {
+                "Ocurrió un error inesperado.".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::Database(e) => write!(f, "{}: {}", self.code(), e),
+            BankError::Internal(message) => write!(f, "{}: {}", self.code(), message),
+            _ => write!(f, "{}: {}", self.code(), self.detail()),
+        }
+    }
+}
+
+impl std::error::Error for BankError {}
+
+impl From<sqlx::Error> for BankError {
+    fn from(e: sqlx::Error) -> Self {
+        BankError::Database(e)
+    }
+}
+
+impl ResponseError for BankError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BankError::InvalidRequest(_)
+            | BankError::InvalidAmount(_)
+            | BankError::InsufficientFunds
+            | BankError::UnknownRecipient
+            | BankError::UnknownUser
+            | BankError::SelfTransfer
+            | BankError::WeakPassword(_)
+            | BankError::InvalidInviteCode
+            | BankError::InvalidResetToken => StatusCode::BAD_REQUEST,
+            BankError::UsernameTaken => StatusCode::CONFLICT,
+            BankError::AccountNotFound | BankError::SessionNotFound => StatusCode::NOT_FOUND,
+            BankError::InvalidCredentials
+            | BankError::InvalidTwoFactorCode
+            | BankError::Unauthorized => StatusCode::UNAUTHORIZED,
+            BankError::Forbidden
+            | BankError::UserDisabled
+            | BankError::TwoFactorEnrollmentRequired => StatusCode::FORBIDDEN,
+            BankError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            BankError::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            BankError::Database(_) | BankError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let BankError::Database(_) | BankError::Internal(_) = self {
+            tracing::error!(error = %self, "error interno");
+        }
+
+        let mut response = HttpResponse::build(self.status_code());
+        response.insert_header((header::CONTENT_TYPE, "application/problem+json"));
+
+        if let BankError::TooManyAttempts(retry_after) = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+        }
+
+        response.body(serde_json::to_string(&self.problem_details()).unwrap_or_default())
+    }
+}
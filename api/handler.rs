@@ -0,0 +1,21 @@
+// src/handler.rs
+
+use tokio::sync::OnceCell;
+use vercel_runtime::{run, Body, Error, Request, Response};
+
+use friendbank::serverless::ServerlessApp;
+
+// Se crea con la primera invocación y dura lo que dure la instancia
+static APP: OnceCell<ServerlessApp> = OnceCell::const_new();
+
+// Función de Vercel. `vercel.json` reescribe todas las rutas hacia ella y el enrutado lo
+// hace la misma aplicación de actix que usa `deposit`.
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+async fn handler(request: Request) -> Result<Response<Body>, Error> {
+    let app = APP.get_or_try_init(|| async { ServerlessApp::from_env() }).await?;
+    app.handle(request).await
+}
@@ -0,0 +1,168 @@
+// src/lib.rs
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::{get, middleware as actix_middleware, web, App, Responder};
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::mysql::{MySqlConnection, MySqlPool, MySqlPoolOptions};
+use sqlx::Executor;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub mod config;
+pub mod errors;
+pub mod logging;
+pub mod metrics;
+pub mod models;
+pub mod api;
+pub mod middleware;
+pub mod notifier;
+pub mod repository;
+pub mod serverless;
+pub mod services;
+
+// Migraciones embebidas en el binario desde ./migrations
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+// Adapta al esquema de 0001 una base de datos anterior a las migraciones
+const EXISTING_SCHEMA: &str = include_str!("../migrations/legacy/existing_schema.sql");
+
+// Aplica las migraciones pendientes. Una base anterior a las migraciones (con las tablas
+// pero sin `_sqlx_migrations`) se adapta antes con `EXISTING_SCHEMA`, y 0001 queda
+// registrada como aplicada para no crear otra vez tablas que ya existen.
+pub async fn run_migrations(pool: &MySqlPool) -> Result<(), MigrateError> {
+    let mut conn = pool.acquire().await?;
+
+    // Con varias instancias arrancando a la vez, solo una adapta la base
+    conn.lock().await?;
+    let adopted = adopt_existing_schema(&mut conn).await;
+    conn.unlock().await?;
+    adopted?;
+
+    MIGRATOR.run(pool).await
+}
+
+async fn adopt_existing_schema(conn: &mut MySqlConnection) -> Result<(), MigrateError> {
+    if table_exists(conn, "_sqlx_migrations").await? || !table_exists(conn, "users").await? {
+        return Ok(());
+    }
+
+    tracing::info!("adaptando una base de datos anterior a las migraciones");
+    conn.execute(EXISTING_SCHEMA).await?;
+
+    // El mismo registro que deja sqlx al aplicar 0001, con su checksum
+    let initial = MIGRATOR
+        .iter()
+        .find(|migration| migration.version == 1)
+        .ok_or(MigrateError::VersionMissing(1))?;
+    conn.ensure_migrations_table().await?;
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (?, ?, TRUE, ?, 0)",
+    )
+    .bind(initial.version)
+    .bind(&*initial.description)
+    .bind(&*initial.checksum)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn table_exists(conn: &mut MySqlConnection, table: &str) -> Result<bool, sqlx::Error> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = DATABASE() AND table_name = ?",
+    )
+    .bind(table)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(count > 0)
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub store: Arc<dyn repository::Store>,
+    pub notifier: Arc<dyn notifier::Notifier>,
+    pub config: Arc<config::Config>,
+    pub tokens: Arc<services::token_service::TokenSettings>,
+    pub metrics: Arc<metrics::Metrics>,
+}
+
+impl AppState {
+    // Falla si las claves JWT de la configuración no se pueden cargar
+    pub fn new(
+        store: Arc<dyn repository::Store>,
+        notifier: Arc<dyn notifier::Notifier>,
+        config: config::Config,
+    ) -> Result<AppState, String> {
+        let tokens = services::token_service::TokenSettings::from_config(&config.auth)?;
+
+        Ok(AppState {
+            store,
+            notifier,
+            config: Arc::new(config),
+            tokens: Arc::new(tokens),
+            metrics: Arc::new(metrics::Metrics::new()),
+        })
+    }
+
+    // Estado de producción: MySQL y el notificador elegido en la configuración
+    pub fn production(config: config::Config, pool: MySqlPool) -> Result<AppState, String> {
+        let notifier: Arc<dyn notifier::Notifier> = match config.notifier.kind {
+            config::NotifierKind::File => Arc::new(notifier::FileNotifier::new(config.notifier.file.clone())),
+            config::NotifierKind::Log => Arc::new(notifier::LogNotifier),
+            config::NotifierKind::Webhook => {
+                Arc::new(notifier::WebhookNotifier::new(config.notifier.webhook_url.clone().unwrap_or_default()))
+            }
+        };
+
+        AppState::new(Arc::new(repository::mysql::MySqlStore::new(pool)), notifier, config)
+    }
+}
+
+// Opciones del pool de MySQL; el servidor conecta al arrancar y la función serverless
+// de forma perezosa, con la primera petición que necesita la base de datos
+pub fn pool_options(config: &config::DatabaseConfig) -> MySqlPoolOptions {
+    MySqlPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds))
+}
+
+// La aplicación completa, con sus middlewares. La usan el servidor, la función serverless
+// y las pruebas HTTP, así que las tres atienden las peticiones exactamente igual.
+pub fn build_app(
+    state: AppState,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .app_data(web::Data::new(state))
+        .wrap(actix_middleware::from_fn(middleware::metrics::track_requests))
+        .wrap(actix_middleware::from_fn(middleware::request_id::propagate_request_id))
+        .configure(config_app)
+}
+
+#[get("/")]
+async fn index() -> impl Responder {
+    "Biendvenido a FriendBank"
+}
+
+// Configuración completa de la aplicación; la usan el servidor y las pruebas HTTP
+pub fn config_app(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(|err, _req| {
+        // Devuelve el motivo del rechazo (p. ej. un monto inválido) como problem+json
+        errors::BankError::InvalidRequest(err.to_string()).into()
+    }))
+    .app_data(web::QueryConfig::default().error_handler(|err, _req| {
+        errors::BankError::InvalidRequest(err.to_string()).into()
+    }))
+    .service(index)
+    .configure(api::routes::config_routes);
+}
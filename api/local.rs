@@ -0,0 +1,79 @@
+// src/local.rs
+
+use actix_web::http::StatusCode;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use vercel_runtime::{Body, Request};
+
+use friendbank::config::Config;
+use friendbank::serverless::{ServerlessApp, CLIENT_IP_HEADER};
+
+// Ejecuta en local la función de Vercel: cada petición se convierte en una de Vercel y
+// pasa por `ServerlessApp::handle`, el mismo camino que recorre en producción.
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    dotenv::dotenv().ok();
+
+    let config = Config::load().unwrap_or_else(|e| panic!("Configuración inválida: {}", e));
+    let bind_address = config.server.bind_address.clone();
+    let app = ServerlessApp::from_config(config).unwrap_or_else(|e| panic!("Claves JWT inválidas: {}", e));
+    let app = web::Data::new(app);
+
+    tracing::info!(bind_address = %bind_address, "función serverless escuchando en local");
+
+    HttpServer::new(move || App::new().app_data(app.clone()).default_service(web::to(forward)))
+        .bind(&bind_address)?
+        .run()
+        .await
+}
+
+async fn forward(req: HttpRequest, body: web::Bytes, app: web::Data<ServerlessApp>) -> HttpResponse {
+    let request = match to_vercel_request(&req, body) {
+        Ok(request) => request,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    let response = match app.handle(request).await {
+        Ok(response) => response,
+        Err(e) => return HttpResponse::BadGateway().body(e.to_string()),
+    };
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for (name, value) in response.headers() {
+        builder.append_header((name.as_str(), value.as_bytes()));
+    }
+
+    match response.into_body() {
+        Body::Empty => builder.finish(),
+        Body::Text(text) => builder.body(text),
+        Body::Binary(bytes) => builder.body(bytes),
+    }
+}
+
+// Lo que haría el proxy de Vercel: la URL absoluta, las cabeceras y la IP del cliente en
+// `x-real-ip`, sustituyendo la que pudiera mandar el propio cliente
+fn to_vercel_request(req: &HttpRequest, body: web::Bytes) -> Result<Request, String> {
+    let body = if body.is_empty() { Body::Empty } else { Body::Binary(body.to_vec()) };
+    let mut request = Request::new(body);
+
+    *request.method_mut() = http::Method::from_bytes(req.method().as_str().as_bytes()).map_err(|e| e.to_string())?;
+    *request.uri_mut() = format!("http://{}{}", req.connection_info().host(), req.uri())
+        .parse()
+        .map_err(|e: http::uri::InvalidUri| e.to_string())?;
+
+    for (name, value) in req.headers() {
+        if name.as_str() == CLIENT_IP_HEADER {
+            continue;
+        }
+        request.headers_mut().append(
+            http::HeaderName::from_bytes(name.as_str().as_bytes()).map_err(|e| e.to_string())?,
+            http::HeaderValue::from_bytes(value.as_bytes()).map_err(|e| e.to_string())?,
+        );
+    }
+    if let Some(addr) = req.peer_addr() {
+        let ip = http::HeaderValue::from_str(&addr.ip().to_string()).map_err(|e| e.to_string())?;
+        request.headers_mut().insert(CLIENT_IP_HEADER, ip);
+    }
+
+    Ok(request)
+}
@@ -0,0 +1,64 @@
+// src/logging.rs
+
+use std::fmt;
+
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+// Lo que se escribe en el log en lugar de una contraseña, un hash o un token
+pub const REDACTED: &str = "[REDACTED]";
+
+// Instala el subscriber global. `RUST_LOG`, si está definida, tiene prioridad sobre
+// `logging.level`. Llamarla dos veces no hace nada.
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.level))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    // `try_init` falla si ya había uno instalado, p. ej. en las pruebas
+    let _ = match config.format {
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).try_init(),
+        LogFormat::Text => builder.try_init(),
+    };
+}
+
+// Envuelve un valor para que ni `Debug` ni `Display` lo muestren
+pub struct Redacted<T>(pub T);
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::User;
+
+    #[test]
+    fn secrets_never_reach_the_debug_output() {
+        let user = User {
+            id: 7,
+            username: "alice".to_string(),
+            password_hash: "$2b$12$hash-que-no-debe-salir".to_string(),
+            role: "user".to_string(),
+            disabled: false,
+            token_version: 0,
+        };
+
+        let output = format!("{:?} {}", user, Redacted("refresh-token"));
+        assert!(output.contains("alice"));
+        assert!(!output.contains("hash-que-no-debe-salir"));
+        assert!(!output.contains("refresh-token"));
+    }
+}
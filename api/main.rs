@@ -1,51 +1,66 @@
 // src/main.rs
 
-use actix_web::{get, web, App, HttpServer, Responder};
-use sqlx::mysql::MySqlPoolOptions;
-use sqlx::{MySql, Pool};
+use actix_web::HttpServer;
 use dotenv::dotenv;
 use std::env;
+use std::time::Duration;
 
-mod models;
-mod api;
-mod middleware;
-mod services;
-
-#[derive(Clone)]
-struct AppState {
-    db: Pool<MySql>,
-}
-
-#[get("/")]
-async fn index() -> impl Responder {
-    "Biendvenido a FriendBank"
-}
+use friendbank::config::Config;
+use friendbank::logging;
+use friendbank::models::UserData;
+use friendbank::services::admin_service;
+use friendbank::{build_app, pool_options, run_migrations, AppState};
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    println!("DATABASE_URL: {:?}", env::var("DATABASE_URL"));
 
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set in .env file");
+    // Un error de configuración detiene el arranque en vez de aparecer en mitad de una petición
+    let config = Config::load().unwrap_or_else(|e| panic!("Configuración inválida: {}", e));
+    logging::init(&config.logging);
 
-    let db_pool = MySqlPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+    let db_pool = pool_options(&config.database)
+        .connect(&config.database.url)
         .await
         .expect("Failed to create MySql pool.");
 
-    println!("Connected to the database succesfully!");
+    tracing::info!("conectado a la base de datos");
+
+    run_migrations(&db_pool).await.expect("Failed to run database migrations.");
+
+    tracing::info!("migraciones aplicadas");
+
+    // `deposit migrate` solo aplica las migraciones y termina
+    if env::args().nth(1).as_deref() == Some("migrate") {
+        return Ok(());
+    }
+
+    let server_config = config.server.clone();
+    let app_state = AppState::production(config, db_pool)
+        .unwrap_or_else(|e| panic!("Claves JWT inválidas: {}", e));
+
+    // `deposit create-admin <usuario>` crea el primer administrador con la contraseña de ADMIN_PASSWORD
+    if env::args().nth(1).as_deref() == Some("create-admin") {
+        let username = env::args().nth(2).expect("Uso: deposit create-admin <usuario>");
+        let password = env::var("ADMIN_PASSWORD").expect("ADMIN_PASSWORD must be set");
+        let user_data = UserData { username, password };
+
+        admin_service::create_user(app_state.store.as_ref(), None, &user_data, "admin")
+            .await
+            .expect("Failed to create the administrator.");
+
+        tracing::info!(username = %user_data.username, "administrador creado");
+        return Ok(());
+    }
+
+    let mut server = HttpServer::new(move || build_app(app_state.clone()))
+        .keep_alive(Duration::from_secs(server_config.keep_alive_seconds))
+        .client_request_timeout(Duration::from_secs(server_config.client_request_timeout_seconds))
+        .shutdown_timeout(server_config.shutdown_timeout_seconds);
 
-    let app_state = AppState { db: db_pool.clone() };
+    if let Some(workers) = server_config.workers {
+        server = server.workers(workers);
+    }
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(app_state.clone()))
-            .service(index)
-            .configure(api::routes::config_routes)
-    })
-    .bind("127.0.0.1:8080")?
-    .run()
-    .await
+    server.bind(&server_config.bind_address)?.run().await
 }
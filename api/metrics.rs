@@ -0,0 +1,169 @@
+// src/metrics.rs
+
+use std::time::Duration;
+
+use prometheus::{
+    CounterVec, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::errors::BankError;
+use crate::repository::PoolStats;
+use crate::services::idempotency::StoredResponse;
+
+// Operaciones que mueven dinero
+#[derive(Debug, Clone, Copy)]
+pub enum MoneyOperation {
+    Transfer,
+    Deposit,
+    Withdrawal,
+}
+
+impl MoneyOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MoneyOperation::Transfer => "transfer",
+            MoneyOperation::Deposit => "deposit",
+            MoneyOperation::Withdrawal => "withdrawal",
+        }
+    }
+}
+
+// Métricas de la aplicación en formato Prometheus. Cada `AppState` tiene su propio
+// registro, así las pruebas no comparten contadores.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    money_operations: IntCounterVec,
+    money_amount: CounterVec,
+    failed_logins: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Peticiones HTTP atendidas"),
+            &["method", "route", "status"],
+        )
+        .expect("métrica válida");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Duración de las peticiones HTTP"),
+            &["method", "route"],
+        )
+        .expect("métrica válida");
+        let money_operations = IntCounterVec::new(
+            Opts::new("bank_operations_total", "Transferencias, depósitos y retiros por resultado"),
+            &["operation", "outcome"],
+        )
+        .expect("métrica válida");
+        let money_amount = CounterVec::new(
+            Opts::new("bank_operation_amount_total", "Suma de los montos pedidos por operación y resultado"),
+            &["operation", "outcome"],
+        )
+        .expect("métrica válida");
+        let failed_logins = IntCounterVec::new(
+            Opts::new("auth_failed_logins_total", "Logins rechazados por motivo"),
+            &["reason"],
+        )
+        .expect("métrica válida");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Conexiones del pool de MySQL por estado"),
+            &["state"],
+        )
+        .expect("métrica válida");
+        let db_pool_max_connections = IntGauge::new("db_pool_max_connections", "Tamaño máximo del pool de MySQL")
+            .expect("métrica válida");
+
+        registry.register(Box::new(http_requests.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(http_request_duration.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(money_operations.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(money_amount.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(failed_logins.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(db_pool_connections.clone())).expect("métrica sin registrar");
+        registry.register(Box::new(db_pool_max_connections.clone())).expect("métrica sin registrar");
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            money_operations,
+            money_amount,
+            failed_logins,
+            db_pool_connections,
+            db_pool_max_connections,
+        }
+    }
+
+    // `route` es el patrón de la ruta (`/protected/sessions/{id}`), nunca la ruta concreta
+    pub fn observe_http_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    // Las repeticiones con la misma Idempotency-Key se cuentan aparte para no sumar dos
+    // veces el mismo movimiento
+    pub fn record_money_operation(
+        &self,
+        operation: MoneyOperation,
+        amount: Decimal,
+        result: &Result<StoredResponse, BankError>,
+    ) {
+        let outcome = match result {
+            Ok(response) if response.replayed => "replayed".to_string(),
+            Ok(_) => "success".to_string(),
+            Err(e) => e.code().to_lowercase(),
+        };
+        let labels = [operation.as_str(), outcome.as_str()];
+
+        self.money_operations.with_label_values(&labels).inc();
+        self.money_amount
+            .with_label_values(&labels)
+            .inc_by(amount.abs().to_f64().unwrap_or(0.0));
+    }
+
+    // Solo cuentan la contraseña o el código incorrectos, y aparte los intentos que el bloqueo
+    // rechazó sin comprobarlos. Un usuario deshabilitado o un error interno no es un ataque.
+    pub fn record_failed_login(&self, error: &BankError) {
+        let reason = match error {
+            BankError::InvalidCredentials | BankError::InvalidTwoFactorCode => error.code().to_lowercase(),
+            BankError::TooManyAttempts(_) => "throttled".to_string(),
+            _ => return,
+        };
+        self.failed_logins.with_label_values(&[&reason]).inc();
+    }
+
+    // Texto para `/metrics`; el estado del pool se lee en el momento de la consulta
+    pub fn render(&self, pool: Option<PoolStats>) -> Result<String, BankError> {
+        if let Some(pool) = pool {
+            let active = pool.size.saturating_sub(pool.idle);
+            self.db_pool_connections.with_label_values(&["active"]).set(active as i64);
+            self.db_pool_connections.with_label_values(&["idle"]).set(pool.idle as i64);
+            self.db_pool_max_connections.set(pool.max_connections as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| BankError::Internal(format!("No se pudieron codificar las métricas: {}", e)))?;
+
+        String::from_utf8(buffer)
+            .map_err(|e| BankError::Internal(format!("Métricas con UTF-8 inválido: {}", e)))
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
@@ -2,13 +2,13 @@
 
 use actix_web::{
     dev::ServiceRequest,
-    Error, HttpMessage, HttpResponse,
+    web, Error, HttpMessage,
 };
 use actix_web_httpauth::extractors::bearer::BearerAuth;
-use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
-use std::env;
- // <-- Importa esto
+use crate::errors::BankError;
+use crate::services::{authorization, token_service};
+use crate::AppState;
 
 // El "payload" de nuestro JWT, debe ser público.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,45 +16,33 @@ pub struct Claims {
     pub sub: i32,
     pub exp: u64, 
     pub role: String,
+    // Identificador único del token, permite revocarlo antes de que expire
+    pub jti: String,
+    // `users.token_version` al emitir el token; si cambia, el token deja de valer
+    pub ver: i32,
+    // Sesión abierta en el login; si se cierra, el token deja de valer
+    pub sid: String,
 }
 
 // **Este es el único middleware que necesitas para la autenticación**
 pub async fn jwt_auth_middleware(req: ServiceRequest, bearer: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let token = bearer.token();
-    
-    let secret_key = env::var("JWT_SECRET")
-        .expect("JWT_SECRET must be set in .env file");
+    let state = match req.app_data::<web::Data<AppState>>().cloned() {
+        Some(state) => state,
+        None => return Err((BankError::Internal("AppState no está registrado".to_string()).into(), req)),
+    };
 
-    let claims = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret_key.as_ref()),
-        &Validation::new(jsonwebtoken::Algorithm::default()),
-    );
+    let claims = match token_service::decode_access_token(&state.tokens, bearer.token()) {
+        Ok(claims) => claims,
+        Err(e) => return Err((e.into(), req)),
+    };
 
-    match claims {
-        Ok(token_data) => {
-            // Inserta las Claims en las extensiones del request para que los handlers puedan acceder a ellas
-            req.extensions_mut().insert(token_data.claims);
-            Ok(req)
-        },
-        Err(_) => {
-            let err = actix_web::error::ErrorUnauthorized("Acceso no autorizado");
-            Err((err, req))
-        }
-    }
-}
-
-pub async fn verify_accountant_role(
-    req: &ServiceRequest,
-) -> Result<(), HttpResponse> {
-// A more idiomatic way to handle the check
-if let Some(claims) = req.extensions().get::<Claims>() {
-    if claims.role != "accountant" {
-        return Err(HttpResponse::Forbidden().finish());
-    }
-} else {
-    return Err(HttpResponse::Unauthorized().finish());
-}
+    // Los tokens cerrados con /auth/logout se rechazan aunque la firma siga siendo válida
+    let user = match authorization::authenticate(state.store.as_ref(), claims).await {
+        Ok(user) => user,
+        Err(e) => return Err((e.into(), req)),
+    };
 
-    Ok(())
+    // Deja el usuario en las extensiones para el extractor `AuthenticatedUser` y `RequirePermission`
+    req.extensions_mut().insert(user);
+    Ok(req)
 }
@@ -0,0 +1,59 @@
+// src/middleware/metrics.rs
+
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+
+use crate::AppState;
+
+// Etiqueta de las peticiones que no casan con ninguna ruta, para que un escaneo de rutas
+// no cree una serie nueva por cada URL
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+// Cuenta y cronometra cada petición por método, patrón de ruta y código de estado.
+// Se registra en la App con `middleware::from_fn(track_requests)`.
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let metrics = req
+        .app_data::<web::Data<AppState>>()
+        .map(|state| state.metrics.clone());
+    let method = method_label(req.method());
+    let started = Instant::now();
+
+    let result = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        let (route, status) = match &result {
+            Ok(res) => (
+                res.request()
+                    .match_pattern()
+                    .unwrap_or_else(|| UNMATCHED_ROUTE.to_string()),
+                res.status(),
+            ),
+            Err(e) => (UNMATCHED_ROUTE.to_string(), e.as_response_error().status_code()),
+        };
+        metrics.observe_http_request(method, &route, status.as_u16(), started.elapsed());
+    }
+
+    result
+}
+
+// Los métodos no estándar se agrupan por el mismo motivo que las rutas desconocidas
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::PATCH => "PATCH",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        _ => "OTHER",
+    }
+}
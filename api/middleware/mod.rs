@@ -1 +1,4 @@
 pub mod jwt_auth;
+pub mod rbac;
+pub mod metrics;
+pub mod request_id;
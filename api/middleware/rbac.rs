@@ -0,0 +1,114 @@
+// src/middleware/rbac.rs
+
+use std::collections::HashSet;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, FromRequest, HttpMessage, HttpRequest,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::errors::BankError;
+use crate::models::Permission;
+
+// Usuario autenticado por `jwt_auth_middleware`, con los permisos de su rol ya cargados
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+    pub role: String,
+    pub jti: String,
+    pub session_id: String,
+    pub permissions: HashSet<Permission>,
+    // Su rol exige 2FA y aún no la ha activado: solo puede usar las rutas sin permiso
+    // (entre ellas el alta en /protected/2fa)
+    pub two_factor_pending: bool,
+}
+
+impl AuthenticatedUser {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+// Lee el usuario que el middleware dejó en las extensiones; sin él la petición no está autenticada
+impl FromRequest for AuthenticatedUser {
+    type Error = BankError;
+    type Future = Ready<Result<AuthenticatedUser, BankError>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedUser>()
+                .cloned()
+                .ok_or(BankError::Unauthorized),
+        )
+    }
+}
+
+// Middleware que exige un permiso. Debe ir por dentro de `jwt_auth_middleware`.
+pub struct RequirePermission {
+    permission: Permission,
+}
+
+impl RequirePermission {
+    pub fn new(permission: Permission) -> RequirePermission {
+        RequirePermission { permission }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Permission,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let denied = match req.extensions().get::<AuthenticatedUser>() {
+            Some(user) if user.two_factor_pending => Some(BankError::TwoFactorEnrollmentRequired),
+            Some(user) if user.has_permission(self.permission) => None,
+            Some(_) => Some(BankError::Forbidden),
+            None => Some(BankError::Unauthorized),
+        };
+
+        // Se responde aquí en vez de devolver el error, para que los middlewares que envuelven
+        // la App vean la respuesta como cualquier otra
+        if let Some(e) = denied {
+            return Box::pin(async move { Ok(req.error_response(e).map_into_right_body()) });
+        }
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move { Ok(service.call(req).await?.map_into_left_body()) })
+    }
+}
@@ -0,0 +1,76 @@
+// src/middleware/request_id.rs
+
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Límite del identificador que se acepta del cliente o del proxy
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// Identificador de la petición, disponible en las extensiones para quien lo necesite
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+// Reutiliza el `X-Request-Id` que llega (si es razonable) o genera uno, abre un span con él
+// para todo lo que se registre durante la petición y lo devuelve en la respuesta.
+// Se registra en la App con `middleware::from_fn(propagate_request_id)`, por fuera del resto.
+pub async fn propagate_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    // Solo el método y el patrón de la ruta: la URL concreta y las cabeceras pueden
+    // llevar datos que no deben acabar en el log
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        route = tracing::field::Empty,
+    );
+    let started = Instant::now();
+
+    let mut res = next.call(req).instrument(span.clone()).await?;
+
+    let route = res.request().match_pattern();
+    span.record("route", route.as_deref().unwrap_or("unmatched"));
+    let status = res.status().as_u16();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    span.in_scope(|| {
+        if res.status().is_server_error() {
+            tracing::error!(status, duration_ms, "petición completada");
+        } else {
+            tracing::info!(status, duration_ms, "petición completada");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    Ok(res)
+}
+
+// Evita que un valor arbitrario del cliente inyecte líneas o basura en el log
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
@@ -1,37 +1,569 @@
 // src/models.rs
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Serialize, Deserialize)]
+use crate::logging::Redacted;
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserData {
+    #[schema(example = "alice")]
+    pub username: String,
+    #[schema(example = "una-contraseña-larga")]
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterRequest {
+    #[schema(example = "alice")]
     pub username: String,
+    #[schema(example = "una-contraseña-larga")]
     pub password: String,
+    // Obligatorio salvo con `registration.require_invite_code = false`
+    #[schema(example = "k3v9-x2qa")]
+    pub invite_code: Option<String>,
 }
 
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, Clone)]
 pub struct User {
     pub id: i32,
     pub username: String,
     pub password_hash: String,
     pub role: String,
+    pub disabled: bool,
+    pub token_version: i32,
+}
+
+// Sin el hash de la contraseña, para que no acabe en el log
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("username", &self.username)
+            .field("password_hash", &Redacted(&self.password_hash))
+            .field("role", &self.role)
+            .field("disabled", &self.disabled)
+            .field("token_version", &self.token_version)
+            .finish()
+    }
+}
+
+// Respuesta que solo lleva un mensaje para mostrar
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MessageResponse {
+    #[schema(example = "Transferencia realizada con éxito")]
+    pub message: String,
+}
+
+impl MessageResponse {
+    pub fn new(message: &str) -> MessageResponse {
+        MessageResponse { message: message.to_string() }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct UserCreated {
+    #[schema(example = "Usuario registrado exitosamente!")]
+    pub message: String,
+    pub user_id: i32,
+}
+
+// Login completo: el access token y el refresh token de la nueva sesión
+#[derive(Serialize, Debug, ToSchema)]
+pub struct LoginResponse {
+    #[schema(example = "Login exitoso")]
+    pub message: String,
+    pub token: String,
+    pub refresh_token: String,
+    // Segundos de vida del access token
+    #[schema(example = 7200)]
+    pub expires_in: i64,
+    #[schema(example = "user")]
+    pub role: String,
+    // El rol exige 2FA y el usuario aún no la ha activado
+    pub two_factor_enrollment_required: bool,
+}
+
+// La contraseña es correcta pero falta el código de /auth/login/2fa
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TwoFactorChallenge {
+    #[schema(example = "Introduce el código de verificación")]
+    pub message: String,
+    pub two_factor_required: bool,
+    pub challenge: String,
+    #[schema(example = 300)]
+    pub expires_in: i64,
+}
+
+// Lo que puede devolver /auth/login con la contraseña correcta
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Tokens(LoginResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RefreshedTokens {
+    pub token: String,
+    pub refresh_token: String,
+    #[schema(example = 7200)]
+    pub expires_in: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RecoveryCodes {
+    #[schema(example = "Verificación en dos pasos activada. Guarda los códigos de recuperación.")]
+    pub message: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TemporaryPassword {
+    pub temporary_password: String,
 }
 
-#[derive(sqlx::FromRow, Debug)]
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordResetRequest {
+    pub username: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct PasswordReset {
+    pub id: i32,
+    pub user_id: i32,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow, Clone)]
+pub struct TotpSecret {
+    pub user_id: i32,
+    pub secret: String,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+}
+
+impl fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TotpSecret")
+            .field("user_id", &self.user_id)
+            .field("secret", &Redacted(&self.secret))
+            .field("enabled_at", &self.enabled_at)
+            .field("last_used_step", &self.last_used_step)
+            .finish()
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct LoginChallenge {
+    pub id: i32,
+    pub user_id: i32,
+    pub expires_at: DateTime<Utc>,
+    pub failed_attempts: i32,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorCode {
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorLogin {
+    pub challenge: String,
+    // Código TOTP o uno de los códigos de recuperación
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorDisable {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TwoFactorPolicy {
+    pub required: bool,
+}
+
+// Vista de un usuario para la administración, sin el hash de la contraseña
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, ToSchema)]
+pub struct UserSummary {
+    pub id: i32,
+    pub username: String,
+    pub role: String,
+    pub disabled: bool,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, ToSchema)]
+pub struct InviteCode {
+    pub id: i32,
+    pub code: String,
+    pub created_by: i32,
+    pub used_by: Option<i32>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RoleChange {
+    pub role: String,
+}
+
+// Permisos tal como se guardan en la tabla `permissions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    TransferCreate,
+    AccountRead,
+    AccountReadAny,
+    CashDeposit,
+    CashWithdraw,
+    AuditRead,
+    UsersManage,
+    InvitesCreate,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::TransferCreate => "transfer:create",
+            Permission::AccountRead => "account:read",
+            Permission::AccountReadAny => "account:read_any",
+            Permission::CashDeposit => "cash:deposit",
+            Permission::CashWithdraw => "cash:withdraw",
+            Permission::AuditRead => "audit:read",
+            Permission::UsersManage => "users:manage",
+            Permission::InvitesCreate => "invites:create",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Permission> {
+        match name {
+            "transfer:create" => Some(Permission::TransferCreate),
+            "account:read" => Some(Permission::AccountRead),
+            "account:read_any" => Some(Permission::AccountReadAny),
+            "cash:deposit" => Some(Permission::CashDeposit),
+            "cash:withdraw" => Some(Permission::CashWithdraw),
+            "audit:read" => Some(Permission::AuditRead),
+            "users:manage" => Some(Permission::UsersManage),
+            "invites:create" => Some(Permission::InvitesCreate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct RefreshToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub family_id: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone)]
+pub struct Session {
+    pub id: String,
+    pub user_id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub terminated_at: Option<DateTime<Utc>>,
+}
+
+// Desde dónde se abre una sesión, tal como lo ve el servidor
+#[derive(Debug, Clone, Default)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    pub refresh_token: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
 pub struct Account {
     pub id: i32,
     pub user_id: i32,
     pub balance: Decimal,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, ToSchema)]
+pub struct AccountBalance {
+    pub id: i32,
+    pub user_id: i32,
+    pub balance: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Acepta montos como texto ("10.50") o como número (10.50) sin pasar por f64
+fn deserialize_amount<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    rust_decimal::serde::arbitrary_precision::deserialize(deserializer)
+        .map_err(|_| de::Error::custom("El monto debe ser un número decimal válido"))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct AccountantData {
+    #[schema(example = "alice")]
     pub username: String,
-    pub amount: f64,
+    #[serde(deserialize_with = "deserialize_amount")]
+    #[schema(example = "50.00")]
+    pub amount: Decimal,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct TransactionData {
+    #[schema(example = "bob")]
     pub recipient_username: String,
-    pub amount: f64,
+    #[serde(deserialize_with = "deserialize_amount")]
+    #[schema(example = "10.50")]
+    pub amount: Decimal,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    pub cursor: Option<i32>,
+    pub limit: Option<u32>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub direction: Option<Direction>,
+    pub counterparty: Option<String>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, ToSchema)]
+pub struct HistoryEntry {
+    pub id: i32,
+    #[schema(example = "10.50")]
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "out")]
+    pub direction: String,
+    #[schema(example = "bob")]
+    pub counterparty: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HistoryPage {
+    pub items: Vec<HistoryEntry>,
+    pub next_cursor: Option<i32>,
+}
+
+// Tipo de asiento, igual que el ENUM de `journal_entries.kind`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Transfer,
+    Deposit,
+    Withdrawal,
+}
+
+impl EntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Transfer => "transfer",
+            EntryKind::Deposit => "deposit",
+            EntryKind::Withdrawal => "withdrawal",
+        }
+    }
+}
+
+// Apunte con signo sobre una cuenta: positivo suma al saldo, negativo resta
+#[derive(Debug, Clone, Copy)]
+pub struct Posting {
+    pub account_id: i32,
+    pub amount: Decimal,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, ToSchema)]
+pub struct UnbalancedEntry {
+    pub journal_entry_id: i32,
+    pub total: Decimal,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, ToSchema)]
+pub struct MismatchedAccount {
+    pub account_id: i32,
+    pub balance: Decimal,
+    pub derived_balance: Decimal,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct LedgerReport {
+    pub consistent: bool,
+    pub unbalanced_entries: Vec<UnbalancedEntry>,
+    pub mismatched_accounts: Vec<MismatchedAccount>,
+    pub total_supply: Decimal,
+    pub reserve_balance: Decimal,
+}
+
+// Acción registrada en `audit_log.action`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    Deposit,
+    Withdrawal,
+    UserCreated,
+    RoleChanged,
+    UserDisabled,
+    UserEnabled,
+    CredentialsReset,
+    PasswordChanged,
+    PasswordReset,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
+    RolePolicyChanged,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Deposit => "deposit",
+            AuditAction::Withdrawal => "withdrawal",
+            AuditAction::UserCreated => "user_created",
+            AuditAction::RoleChanged => "role_changed",
+            AuditAction::UserDisabled => "user_disabled",
+            AuditAction::UserEnabled => "user_enabled",
+            AuditAction::CredentialsReset => "credentials_reset",
+            AuditAction::PasswordChanged => "password_changed",
+            AuditAction::PasswordReset => "password_reset",
+            AuditAction::TwoFactorEnabled => "two_factor_enabled",
+            AuditAction::TwoFactorDisabled => "two_factor_disabled",
+            AuditAction::RolePolicyChanged => "role_policy_changed",
+        }
+    }
+}
+
+// Entrada del log de auditoría: quién (`actor`) hizo qué y, si aplica, sobre quién
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub actor_user_id: i32,
+    pub target_user_id: Option<i32>,
+    pub amount: Option<Decimal>,
+    pub details: Option<serde_json::Value>,
+}
+
+// Sujeto al que se le cuentan los fallos de login
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThrottleScope {
+    Username,
+    Ip,
+}
+
+impl ThrottleScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThrottleScope::Username => "username",
+            ThrottleScope::Ip => "ip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthEventKind {
+    LoginFailed,
+    LoginBlocked,
+    AccountLocked,
+    IpLocked,
+    PasswordChangeFailed,
+    PasswordResetRequested,
+}
+
+impl AuthEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthEventKind::LoginFailed => "login_failed",
+            AuthEventKind::LoginBlocked => "login_blocked",
+            AuthEventKind::AccountLocked => "account_locked",
+            AuthEventKind::IpLocked => "ip_locked",
+            AuthEventKind::PasswordChangeFailed => "password_change_failed",
+            AuthEventKind::PasswordResetRequested => "password_reset_requested",
+        }
+    }
+}
+
+// Entrada del log de autenticación; `user_id` solo se conoce si el usuario existe
+#[derive(Debug, Clone)]
+pub struct AuthEvent {
+    pub kind: AuthEventKind,
+    pub username: Option<String>,
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, Clone, ToSchema)]
+pub struct AuthEventRecord {
+    pub id: i32,
+    #[schema(example = "login_failed")]
+    pub event: String,
+    pub username: Option<String>,
+    pub user_id: Option<i32>,
+    pub ip_address: Option<String>,
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthEventQuery {
+    pub limit: Option<u32>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub request_hash: String,
+    pub response_status: u16,
+    pub response_body: serde_json::Value,
+}
+
+// Filas de las tablas que las migraciones crean con una sola fila
+#[derive(sqlx::FromRow, Debug, Clone, Copy)]
+pub struct SingletonRowCounts {
+    pub total_supply: i64,
+    pub transaction_count: i64,
 }
@@ -0,0 +1,161 @@
+// src/notifier.rs
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::errors::BankError;
+
+// Mensajes que el banco envía a sus usuarios
+#[derive(Debug, Clone)]
+pub enum Notification {
+    PasswordReset {
+        username: String,
+        token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+impl Notification {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::PasswordReset { .. } => "password_reset",
+        }
+    }
+
+    pub fn recipient(&self) -> &str {
+        match self {
+            Notification::PasswordReset { username, .. } => username,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        match self {
+            Notification::PasswordReset { username, token, expires_at } => format!(
+                "Para: {}\nTu código para restablecer la contraseña es {} (válido hasta {}).",
+                username,
+                token,
+                expires_at.to_rfc3339()
+            ),
+        }
+    }
+
+    // Cuerpo que recibe el webhook, con el código en claro para que lo entregue
+    pub fn to_json(&self) -> Value {
+        match self {
+            Notification::PasswordReset { username, token, expires_at } => json!({
+                "type": self.kind(),
+                "username": username,
+                "token": token,
+                "expires_at": expires_at.to_rfc3339(),
+            }),
+        }
+    }
+}
+
+// Canal de entrega de las notificaciones (correo, SMS...). Cada despliegue elige el suyo.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), BankError>;
+}
+
+// Para desarrollo: deja constancia en el log de que se envió, pero nunca el código
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), BankError> {
+        info!(kind = notification.kind(), to = notification.recipient(), "notificación enviada");
+        Ok(())
+    }
+}
+
+// Para desarrollo: añade las notificaciones al final de un fichero
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> FileNotifier {
+        FileNotifier { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), BankError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| BankError::Internal(format!("no se pudo abrir {}: {}", self.path.display(), e)))?;
+
+        writeln!(file, "{}\n", notification.render())
+            .map_err(|e| BankError::Internal(format!("no se pudo escribir la notificación: {}", e)))
+    }
+}
+
+// Para producción: publica la notificación en JSON en un servicio que la entrega (correo, SMS...)
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> WebhookNotifier {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("no se pudo crear el cliente HTTP");
+
+        WebhookNotifier { url: url.into(), client }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), BankError> {
+        // El error de reqwest no lleva el cuerpo, así que el código no acaba en el log
+        self.client
+            .post(&self.url)
+            .json(&notification.to_json())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| BankError::Internal(format!("no se pudo enviar la notificación: {}", e)))?;
+
+        info!(kind = notification.kind(), to = notification.recipient(), "notificación enviada");
+        Ok(())
+    }
+}
+
+// Para pruebas: guarda las notificaciones para poder inspeccionarlas
+#[derive(Clone, Default)]
+pub struct MemoryNotifier {
+    sent: Arc<Mutex<Vec<Notification>>>,
+}
+
+impl MemoryNotifier {
+    pub fn new() -> MemoryNotifier {
+        MemoryNotifier::default()
+    }
+
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.lock().expect("notificador envenenado").clone()
+    }
+}
+
+#[async_trait]
+impl Notifier for MemoryNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), BankError> {
+        self.sent.lock().expect("notificador envenenado").push(notification.clone());
+        Ok(())
+    }
+}
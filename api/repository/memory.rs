@@ -0,0 +1,958 @@
+// src/repository/memory.rs
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::errors::BankError;
+use crate::models::{
+    Account, AccountBalance, AuditEntry, AuthEvent, AuthEventRecord, Direction, EntryKind,
+    HistoryEntry, HistoryQuery, IdempotencyRecord, InviteCode, LoginChallenge, MismatchedAccount,
+    PasswordReset, Posting, RefreshToken, Session, SingletonRowCounts, ThrottleScope, TotpSecret,
+    UnbalancedEntry, User, UserSummary,
+};
+use crate::repository::{
+    AccountRepository, AuditRepository, HealthRepository, InviteRepository,
+    LoginThrottleRepository, PasswordResetRepository, SessionRepository, Store, TokenRepository,
+    TransactionRepository, TwoFactorRepository, UnitOfWork, UserRepository,
+};
+
+// Los importes se guardan con la misma escala que DECIMAL(19, 2) en MySQL
+const AMOUNT_SCALE: u32 = 2;
+
+fn stored_amount(amount: Decimal) -> Decimal {
+    let mut stored = amount;
+    stored.rescale(AMOUNT_SCALE);
+    stored
+}
+
+// Misma asignación inicial que la migración de permisos
+const DEFAULT_ROLE_PERMISSIONS: &[(&str, &str)] = &[
+    ("user", "transfer:create"),
+    ("user", "account:read"),
+    ("accountant", "transfer:create"),
+    ("accountant", "account:read"),
+    ("accountant", "account:read_any"),
+    ("accountant", "cash:deposit"),
+    ("accountant", "cash:withdraw"),
+    ("accountant", "audit:read"),
+    ("admin", "users:manage"),
+    ("admin", "account:read_any"),
+    ("admin", "audit:read"),
+    ("user", "invites:create"),
+    ("accountant", "invites:create"),
+    ("admin", "invites:create"),
+];
+
+#[derive(Clone)]
+struct MemoryAccount {
+    id: i32,
+    // `None` para la cuenta de reserva del banco
+    user_id: Option<i32>,
+    balance: Decimal,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+struct MemoryTransaction {
+    id: i32,
+    sender_id: i32,
+    recipient_id: i32,
+    amount: Decimal,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+struct MemoryLoginAttempts {
+    failures: i32,
+    last_failure_at: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone)]
+struct MemoryPosting {
+    journal_entry_id: i32,
+    posting: Posting,
+}
+
+#[derive(Clone)]
+struct MemoryState {
+    users: Vec<User>,
+    accounts: Vec<MemoryAccount>,
+    transactions: Vec<MemoryTransaction>,
+    journal_entries: Vec<(i32, EntryKind, i32)>,
+    postings: Vec<MemoryPosting>,
+    audit_log: Vec<AuditEntry>,
+    idempotency_keys: HashMap<(i32, String), IdempotencyRecord>,
+    // Pares (rol, permiso), como `role_permissions`
+    role_permissions: Vec<(String, String)>,
+    // Tokens de refresco indexados por el hash del token
+    refresh_tokens: HashMap<String, RefreshToken>,
+    revoked_tokens: HashMap<String, DateTime<Utc>>,
+    invite_codes: Vec<InviteCode>,
+    // Restablecimientos de contraseña indexados por el hash del token
+    password_resets: HashMap<String, PasswordReset>,
+    totp_secrets: HashMap<i32, TotpSecret>,
+    // (usuario, hash del código, usado)
+    recovery_codes: Vec<(i32, String, bool)>,
+    // Desafíos de login indexados por el hash del token
+    login_challenges: HashMap<String, LoginChallenge>,
+    // Roles que exigen verificación en dos pasos
+    two_factor_roles: Vec<String>,
+    login_attempts: HashMap<(ThrottleScope, String), MemoryLoginAttempts>,
+    auth_events: Vec<AuthEventRecord>,
+    sessions: HashMap<String, Session>,
+    total_supply: Decimal,
+    transaction_count: i64,
+}
+
+impl MemoryState {
+    // Mismo punto de partida que las migraciones: la reserva y las filas únicas
+    fn new() -> MemoryState {
+        MemoryState {
+            users: Vec::new(),
+            accounts: vec![MemoryAccount {
+                id: 1,
+                user_id: None,
+                balance: stored_amount(Decimal::ZERO),
+                updated_at: Utc::now(),
+            }],
+            transactions: Vec::new(),
+            journal_entries: Vec::new(),
+            postings: Vec::new(),
+            audit_log: Vec::new(),
+            idempotency_keys: HashMap::new(),
+            role_permissions: DEFAULT_ROLE_PERMISSIONS
+                .iter()
+                .map(|(role, permission)| (role.to_string(), permission.to_string()))
+                .collect(),
+            refresh_tokens: HashMap::new(),
+            revoked_tokens: HashMap::new(),
+            invite_codes: Vec::new(),
+            password_resets: HashMap::new(),
+            totp_secrets: HashMap::new(),
+            recovery_codes: Vec::new(),
+            login_challenges: HashMap::new(),
+            two_factor_roles: Vec::new(),
+            login_attempts: HashMap::new(),
+            auth_events: Vec::new(),
+            sessions: HashMap::new(),
+            total_supply: stored_amount(Decimal::ZERO),
+            transaction_count: 0,
+        }
+    }
+
+    fn username(&self, user_id: i32) -> Option<&str> {
+        self.users
+            .iter()
+            .find(|user| user.id == user_id)
+            .map(|user| user.username.as_str())
+    }
+
+    fn reserve(&self) -> &MemoryAccount {
+        self.accounts
+            .iter()
+            .find(|account| account.user_id.is_none())
+            .expect("la reserva se crea junto con el estado")
+    }
+}
+
+// Almacenamiento en memoria para pruebas. Las unidades de trabajo se ejecutan de una en
+// una (equivale a bloquear todas las filas) y se revierten si no se confirman.
+#[derive(Clone)]
+pub struct MemoryStore {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore {
+            state: Arc::new(Mutex::new(MemoryState::new())),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, BankError> {
+        let state = self.state.clone().lock_owned().await;
+        let snapshot = Some(state.clone());
+        Ok(Box::new(MemoryUnitOfWork { state, snapshot }))
+    }
+}
+
+pub struct MemoryUnitOfWork {
+    state: OwnedMutexGuard<MemoryState>,
+    // Copia tomada al empezar; se restaura si la unidad de trabajo no se confirma
+    snapshot: Option<MemoryState>,
+}
+
+impl Drop for MemoryUnitOfWork {
+    fn drop(&mut self) {
+        if let Some(snapshot) = self.snapshot.take() {
+            *self.state = snapshot;
+        }
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    async fn commit(mut self: Box<Self>) -> Result<(), BankError> {
+        self.snapshot = None;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryUnitOfWork {
+    async fn find_user_by_username(&mut self, username: &str) -> Result<Option<User>, BankError> {
+        Ok(self.state.users.iter().find(|user| user.username == username).cloned())
+    }
+
+    async fn find_user_by_id(&mut self, user_id: i32) -> Result<Option<User>, BankError> {
+        Ok(self.state.users.iter().find(|user| user.id == user_id).cloned())
+    }
+
+    // Cada unidad de trabajo ya tiene el estado entero para ella sola
+    async fn lock_user(&mut self, _user_id: i32) -> Result<(), BankError> {
+        Ok(())
+    }
+
+    async fn insert_user(&mut self, username: &str, password_hash: &str, role: &str) -> Result<i32, BankError> {
+        if self.state.users.iter().any(|user| user.username == username) {
+            return Err(BankError::UsernameTaken);
+        }
+
+        let id = self.state.users.len() as i32 + 1;
+        self.state.users.push(User {
+            id,
+            username: username.to_string(),
+            password_hash: password_hash.to_string(),
+            role: role.to_string(),
+            disabled: false,
+            token_version: 0,
+        });
+
+        Ok(id)
+    }
+
+    async fn list_users(&mut self) -> Result<Vec<UserSummary>, BankError> {
+        Ok(self
+            .state
+            .users
+            .iter()
+            .map(|user| UserSummary {
+                id: user.id,
+                username: user.username.clone(),
+                role: user.role.clone(),
+                disabled: user.disabled,
+            })
+            .collect())
+    }
+
+    async fn update_user_role(&mut self, user_id: i32, role: &str) -> Result<(), BankError> {
+        if let Some(user) = self.state.users.iter_mut().find(|user| user.id == user_id) {
+            user.role = role.to_string();
+        }
+        Ok(())
+    }
+
+    async fn set_user_disabled(&mut self, user_id: i32, disabled: bool) -> Result<(), BankError> {
+        if let Some(user) = self.state.users.iter_mut().find(|user| user.id == user_id) {
+            user.disabled = disabled;
+        }
+        Ok(())
+    }
+
+    async fn update_password_hash(&mut self, user_id: i32, password_hash: &str) -> Result<(), BankError> {
+        if let Some(user) = self.state.users.iter_mut().find(|user| user.id == user_id) {
+            user.password_hash = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn increment_token_version(&mut self, user_id: i32) -> Result<(), BankError> {
+        if let Some(user) = self.state.users.iter_mut().find(|user| user.id == user_id) {
+            user.token_version += 1;
+        }
+        Ok(())
+    }
+
+    async fn find_role_permissions(&mut self, role: &str) -> Result<Vec<String>, BankError> {
+        Ok(self
+            .state
+            .role_permissions
+            .iter()
+            .filter(|(permission_role, _)| permission_role == role)
+            .map(|(_, permission)| permission.clone())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AccountRepository for MemoryUnitOfWork {
+    async fn insert_account(&mut self, user_id: i32) -> Result<i32, BankError> {
+        let id = self.state.accounts.len() as i32 + 1;
+        self.state.accounts.push(MemoryAccount {
+            id,
+            user_id: Some(user_id),
+            balance: stored_amount(Decimal::ZERO),
+            updated_at: Utc::now(),
+        });
+
+        Ok(id)
+    }
+
+    async fn find_account_by_user(&mut self, user_id: i32) -> Result<Option<AccountBalance>, BankError> {
+        Ok(self
+            .state
+            .accounts
+            .iter()
+            .find(|account| account.user_id == Some(user_id))
+            .map(|account| AccountBalance {
+                id: account.id,
+                user_id,
+                balance: account.balance,
+                updated_at: account.updated_at,
+            }))
+    }
+
+    async fn find_customer_account(&mut self, account_id: i32) -> Result<Option<AccountBalance>, BankError> {
+        Ok(self
+            .state
+            .accounts
+            .iter()
+            .find(|account| account.id == account_id)
+            .and_then(|account| {
+                account.user_id.map(|user_id| AccountBalance {
+                    id: account.id,
+                    user_id,
+                    balance: account.balance,
+                    updated_at: account.updated_at,
+                })
+            }))
+    }
+
+    async fn lock_accounts_by_users(&mut self, user_ids: &[i32]) -> Result<Vec<Account>, BankError> {
+        let mut accounts: Vec<Account> = self
+            .state
+            .accounts
+            .iter()
+            .filter_map(|account| match account.user_id {
+                Some(user_id) if user_ids.contains(&user_id) => Some(Account {
+                    id: account.id,
+                    user_id,
+                    balance: account.balance,
+                }),
+                _ => None,
+            })
+            .collect();
+        accounts.sort_by_key(|account| account.id);
+
+        Ok(accounts)
+    }
+
+    async fn reserve_account_id(&mut self) -> Result<i32, BankError> {
+        Ok(self.state.reserve().id)
+    }
+
+    async fn reserve_balance(&mut self) -> Result<Decimal, BankError> {
+        Ok(self.state.reserve().balance)
+    }
+
+    async fn mismatched_accounts(&mut self) -> Result<Vec<MismatchedAccount>, BankError> {
+        let state = &self.state;
+        Ok(state
+            .accounts
+            .iter()
+            .filter_map(|account| {
+                let derived_balance: Decimal = state
+                    .postings
+                    .iter()
+                    .filter(|p| p.posting.account_id == account.id)
+                    .map(|p| p.posting.amount)
+                    .sum();
+
+                (derived_balance != account.balance).then_some(MismatchedAccount {
+                    account_id: account.id,
+                    balance: account.balance,
+                    derived_balance,
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for MemoryUnitOfWork {
+    async fn insert_transaction(
+        &mut self,
+        sender_id: i32,
+        recipient_id: i32,
+        amount: Decimal,
+    ) -> Result<i32, BankError> {
+        let id = self.state.transactions.len() as i32 + 1;
+        self.state.transactions.push(MemoryTransaction {
+            id,
+            sender_id,
+            recipient_id,
+            amount: stored_amount(amount),
+            created_at: Utc::now(),
+        });
+
+        Ok(id)
+    }
+
+    async fn list_transactions(
+        &mut self,
+        user_id: i32,
+        query: &HistoryQuery,
+        limit: u32,
+    ) -> Result<Vec<HistoryEntry>, BankError> {
+        let state = &self.state;
+        let mut entries: Vec<HistoryEntry> = state
+            .transactions
+            .iter()
+            .filter(|t| t.sender_id == user_id || t.recipient_id == user_id)
+            .filter_map(|t| {
+                let direction = if t.sender_id == user_id { Direction::Out } else { Direction::In };
+                let counterparty_id = if direction == Direction::Out { t.recipient_id } else { t.sender_id };
+                let counterparty = state.username(counterparty_id)?.to_string();
+
+                let matches = query.cursor.is_none_or(|cursor| t.id < cursor)
+                    && query.from.is_none_or(|from| t.created_at >= from)
+                    && query.to.is_none_or(|to| t.created_at < to)
+                    && query.direction.is_none_or(|d| d == direction)
+                    && query.counterparty.as_ref().is_none_or(|name| *name == counterparty)
+                    && query.min_amount.is_none_or(|min| t.amount >= min)
+                    && query.max_amount.is_none_or(|max| t.amount <= max);
+
+                matches.then(|| HistoryEntry {
+                    id: t.id,
+                    amount: t.amount,
+                    created_at: t.created_at,
+                    direction: direction.as_str().to_string(),
+                    counterparty,
+                })
+            })
+            .collect();
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.id));
+        entries.truncate(limit as usize);
+
+        Ok(entries)
+    }
+
+    async fn insert_journal_entry(
+        &mut self,
+        kind: EntryKind,
+        transaction_id: i32,
+        postings: &[Posting],
+    ) -> Result<i32, BankError> {
+        let journal_entry_id = self.state.journal_entries.len() as i32 + 1;
+        self.state.journal_entries.push((journal_entry_id, kind, transaction_id));
+
+        for posting in postings {
+            let account = self
+                .state
+                .accounts
+                .iter_mut()
+                .find(|account| account.id == posting.account_id)
+                .ok_or_else(|| BankError::Internal(format!("cuenta inexistente: {}", posting.account_id)))?;
+            account.balance = stored_amount(account.balance + posting.amount);
+            account.updated_at = Utc::now();
+
+            self.state.postings.push(MemoryPosting { journal_entry_id, posting: *posting });
+        }
+
+        Ok(journal_entry_id)
+    }
+
+    async fn unbalanced_entries(&mut self) -> Result<Vec<UnbalancedEntry>, BankError> {
+        let mut totals: HashMap<i32, Decimal> = HashMap::new();
+        for p in &self.state.postings {
+            *totals.entry(p.journal_entry_id).or_default() += p.posting.amount;
+        }
+
+        let mut entries: Vec<UnbalancedEntry> = totals
+            .into_iter()
+            .filter(|(_, total)| !total.is_zero())
+            .map(|(journal_entry_id, total)| UnbalancedEntry { journal_entry_id, total })
+            .collect();
+        entries.sort_by_key(|entry| entry.journal_entry_id);
+
+        Ok(entries)
+    }
+
+    async fn find_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, BankError> {
+        Ok(self.state.idempotency_keys.get(&(user_id, key.to_string())).cloned())
+    }
+
+    async fn insert_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), BankError> {
+        self.state.idempotency_keys.insert((user_id, key.to_string()), record.clone());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MemoryUnitOfWork {
+    async fn insert_audit_log(&mut self, entry: &AuditEntry) -> Result<(), BankError> {
+        let mut entry = entry.clone();
+        entry.amount = entry.amount.map(stored_amount);
+        self.state.audit_log.push(entry);
+        Ok(())
+    }
+
+    async fn adjust_total_supply(&mut self, delta: Decimal) -> Result<(), BankError> {
+        self.state.total_supply = stored_amount(self.state.total_supply + delta);
+        Ok(())
+    }
+
+    async fn total_supply(&mut self) -> Result<Decimal, BankError> {
+        Ok(self.state.total_supply)
+    }
+
+    async fn insert_auth_event(&mut self, event: &AuthEvent) -> Result<(), BankError> {
+        let id = self.state.auth_events.len() as i32 + 1;
+        self.state.auth_events.push(AuthEventRecord {
+            id,
+            event: event.kind.as_str().to_string(),
+            username: event.username.clone(),
+            user_id: event.user_id,
+            ip_address: event.ip_address.clone(),
+            details: event.details.clone(),
+            created_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    async fn list_auth_events(&mut self, limit: u32) -> Result<Vec<AuthEventRecord>, BankError> {
+        Ok(self.state.auth_events.iter().rev().take(limit as usize).cloned().collect())
+    }
+
+    async fn increment_transaction_count(&mut self) -> Result<(), BankError> {
+        self.state.transaction_count += 1;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenRepository for MemoryUnitOfWork {
+    async fn insert_refresh_token(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        family_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        let id = self.state.refresh_tokens.len() as i32 + 1;
+        self.state.refresh_tokens.insert(
+            token_hash.to_string(),
+            RefreshToken {
+                id,
+                user_id,
+                family_id: family_id.to_string(),
+                expires_at,
+                revoked_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn find_refresh_token(&mut self, token_hash: &str) -> Result<Option<RefreshToken>, BankError> {
+        Ok(self.state.refresh_tokens.get(token_hash).cloned())
+    }
+
+    async fn revoke_refresh_token(&mut self, refresh_token_id: i32) -> Result<(), BankError> {
+        let now = Utc::now();
+        for token in self.state.refresh_tokens.values_mut() {
+            if token.id == refresh_token_id && token.revoked_at.is_none() {
+                token.revoked_at = Some(now);
+            }
+        }
+        Ok(())
+    }
+
+    async fn revoke_refresh_token_family(&mut self, family_id: &str) -> Result<(), BankError> {
+        let now = Utc::now();
+        for token in self.state.refresh_tokens.values_mut() {
+            if token.family_id == family_id && token.revoked_at.is_none() {
+                token.revoked_at = Some(now);
+            }
+        }
+        Ok(())
+    }
+
+    async fn revoke_user_refresh_tokens(&mut self, user_id: i32) -> Result<(), BankError> {
+        let now = Utc::now();
+        for token in self.state.refresh_tokens.values_mut() {
+            if token.user_id == user_id && token.revoked_at.is_none() {
+                token.revoked_at = Some(now);
+            }
+        }
+        Ok(())
+    }
+
+    async fn revoke_access_token(&mut self, jti: &str, expires_at: DateTime<Utc>) -> Result<(), BankError> {
+        self.state.revoked_tokens.entry(jti.to_string()).or_insert(expires_at);
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&mut self, jti: &str) -> Result<bool, BankError> {
+        Ok(self.state.revoked_tokens.contains_key(jti))
+    }
+
+    async fn delete_expired_revoked_tokens(&mut self, now: DateTime<Utc>) -> Result<(), BankError> {
+        self.state.revoked_tokens.retain(|_, expires_at| *expires_at >= now);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl InviteRepository for MemoryUnitOfWork {
+    async fn insert_invite_code(
+        &mut self,
+        code: &str,
+        created_by: i32,
+        expires_at: DateTime<Utc>,
+    ) -> Result<InviteCode, BankError> {
+        let invite_code = InviteCode {
+            id: self.state.invite_codes.len() as i32 + 1,
+            code: code.to_string(),
+            created_by,
+            used_by: None,
+            expires_at,
+            used_at: None,
+        };
+        self.state.invite_codes.push(invite_code.clone());
+        Ok(invite_code)
+    }
+
+    async fn find_invite_code(&mut self, code: &str) -> Result<Option<InviteCode>, BankError> {
+        Ok(self.state.invite_codes.iter().find(|invite| invite.code == code).cloned())
+    }
+
+    async fn mark_invite_code_used(&mut self, invite_code_id: i32, used_by: i32) -> Result<(), BankError> {
+        if let Some(invite) = self.state.invite_codes.iter_mut().find(|invite| invite.id == invite_code_id) {
+            invite.used_by = Some(used_by);
+            invite.used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn list_invite_codes(&mut self, created_by: i32) -> Result<Vec<InviteCode>, BankError> {
+        Ok(self
+            .state
+            .invite_codes
+            .iter()
+            .rev()
+            .filter(|invite| invite.created_by == created_by)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for MemoryUnitOfWork {
+    async fn insert_password_reset(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        let id = self.state.password_resets.len() as i32 + 1;
+        self.state.password_resets.insert(
+            token_hash.to_string(),
+            PasswordReset { id, user_id, expires_at, used_at: None },
+        );
+        Ok(())
+    }
+
+    async fn find_password_reset(&mut self, token_hash: &str) -> Result<Option<PasswordReset>, BankError> {
+        Ok(self.state.password_resets.get(token_hash).cloned())
+    }
+
+    async fn consume_password_resets(&mut self, user_id: i32) -> Result<(), BankError> {
+        let now = Utc::now();
+        for password_reset in self.state.password_resets.values_mut() {
+            if password_reset.user_id == user_id && password_reset.used_at.is_none() {
+                password_reset.used_at = Some(now);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactorRepository for MemoryUnitOfWork {
+    async fn find_totp_secret(&mut self, user_id: i32) -> Result<Option<TotpSecret>, BankError> {
+        Ok(self.state.totp_secrets.get(&user_id).cloned())
+    }
+
+    async fn is_totp_enabled(&mut self, user_id: i32) -> Result<bool, BankError> {
+        Ok(self
+            .state
+            .totp_secrets
+            .get(&user_id)
+            .is_some_and(|totp_secret| totp_secret.enabled_at.is_some()))
+    }
+
+    async fn save_pending_totp_secret(&mut self, user_id: i32, secret: &str) -> Result<(), BankError> {
+        self.state.totp_secrets.insert(
+            user_id,
+            TotpSecret {
+                user_id,
+                secret: secret.to_string(),
+                enabled_at: None,
+                last_used_step: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn enable_totp(&mut self, user_id: i32) -> Result<(), BankError> {
+        if let Some(totp_secret) = self.state.totp_secrets.get_mut(&user_id) {
+            totp_secret.enabled_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn update_totp_last_used_step(&mut self, user_id: i32, step: i64) -> Result<(), BankError> {
+        if let Some(totp_secret) = self.state.totp_secrets.get_mut(&user_id) {
+            totp_secret.last_used_step = Some(step);
+        }
+        Ok(())
+    }
+
+    async fn delete_totp(&mut self, user_id: i32) -> Result<(), BankError> {
+        self.state.totp_secrets.remove(&user_id);
+        self.state.recovery_codes.retain(|(owner, _, _)| *owner != user_id);
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(&mut self, user_id: i32, code_hashes: &[String]) -> Result<(), BankError> {
+        self.state.recovery_codes.retain(|(owner, _, _)| *owner != user_id);
+        self.state
+            .recovery_codes
+            .extend(code_hashes.iter().map(|code_hash| (user_id, code_hash.clone(), false)));
+        Ok(())
+    }
+
+    async fn use_recovery_code(&mut self, user_id: i32, code_hash: &str) -> Result<bool, BankError> {
+        let code = self
+            .state
+            .recovery_codes
+            .iter_mut()
+            .find(|(owner, hash, used)| *owner == user_id && hash == code_hash && !*used);
+
+        match code {
+            Some((_, _, used)) => {
+                *used = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn insert_login_challenge(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        let id = self.state.login_challenges.len() as i32 + 1;
+        self.state.login_challenges.insert(
+            token_hash.to_string(),
+            LoginChallenge { id, user_id, expires_at, failed_attempts: 0, used_at: None },
+        );
+        Ok(())
+    }
+
+    async fn find_login_challenge(&mut self, token_hash: &str) -> Result<Option<LoginChallenge>, BankError> {
+        Ok(self.state.login_challenges.get(token_hash).cloned())
+    }
+
+    async fn record_login_challenge_failure(&mut self, challenge_id: i32) -> Result<(), BankError> {
+        if let Some(challenge) = self.state.login_challenges.values_mut().find(|c| c.id == challenge_id) {
+            challenge.failed_attempts += 1;
+        }
+        Ok(())
+    }
+
+    async fn consume_login_challenge(&mut self, challenge_id: i32) -> Result<(), BankError> {
+        if let Some(challenge) = self.state.login_challenges.values_mut().find(|c| c.id == challenge_id) {
+            challenge.used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    async fn role_requires_two_factor(&mut self, role: &str) -> Result<bool, BankError> {
+        Ok(self.state.two_factor_roles.iter().any(|required_role| required_role == role))
+    }
+
+    async fn set_role_requires_two_factor(&mut self, role: &str, required: bool) -> Result<(), BankError> {
+        self.state.two_factor_roles.retain(|required_role| required_role != role);
+        if required {
+            self.state.two_factor_roles.push(role.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoginThrottleRepository for MemoryUnitOfWork {
+    async fn find_login_lock(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+    ) -> Result<Option<DateTime<Utc>>, BankError> {
+        Ok(self
+            .state
+            .login_attempts
+            .get(&(scope, subject.to_string()))
+            .and_then(|attempts| attempts.locked_until))
+    }
+
+    async fn record_login_failure(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+    ) -> Result<i32, BankError> {
+        let attempts = self
+            .state
+            .login_attempts
+            .entry((scope, subject.to_string()))
+            .or_insert(MemoryLoginAttempts { failures: 0, last_failure_at: now, locked_until: None });
+
+        if attempts.last_failure_at < window_start {
+            attempts.failures = 0;
+        }
+        attempts.failures += 1;
+        attempts.last_failure_at = now;
+
+        Ok(attempts.failures)
+    }
+
+    async fn lock_login_subject(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        until: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        if let Some(attempts) = self.state.login_attempts.get_mut(&(scope, subject.to_string())) {
+            attempts.locked_until = Some(until);
+        }
+        Ok(())
+    }
+
+    async fn clear_login_attempts(&mut self, scope: ThrottleScope, subject: &str) -> Result<(), BankError> {
+        self.state.login_attempts.remove(&(scope, subject.to_string()));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MemoryUnitOfWork {
+    async fn insert_session(
+        &mut self,
+        session_id: &str,
+        user_id: i32,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<(), BankError> {
+        let now = Utc::now();
+        self.state.sessions.insert(
+            session_id.to_string(),
+            Session {
+                id: session_id.to_string(),
+                user_id,
+                user_agent: user_agent.map(str::to_string),
+                ip_address: ip_address.map(str::to_string),
+                created_at: now,
+                last_seen_at: now,
+                terminated_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn find_session(&mut self, session_id: &str) -> Result<Option<Session>, BankError> {
+        Ok(self.state.sessions.get(session_id).cloned())
+    }
+
+    async fn list_active_sessions(&mut self, user_id: i32) -> Result<Vec<Session>, BankError> {
+        let mut sessions: Vec<Session> = self
+            .state
+            .sessions
+            .values()
+            .filter(|session| session.user_id == user_id && session.terminated_at.is_none())
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+        Ok(sessions)
+    }
+
+    async fn touch_session(&mut self, session_id: &str, last_seen_at: DateTime<Utc>) -> Result<(), BankError> {
+        if let Some(session) = self.state.sessions.get_mut(session_id) {
+            session.last_seen_at = last_seen_at;
+        }
+        Ok(())
+    }
+
+    async fn terminate_session(&mut self, session_id: &str) -> Result<(), BankError> {
+        if let Some(session) = self.state.sessions.get_mut(session_id) {
+            session.terminated_at.get_or_insert_with(Utc::now);
+        }
+        Ok(())
+    }
+
+    async fn terminate_user_sessions(&mut self, user_id: i32) -> Result<(), BankError> {
+        let now = Utc::now();
+        for session in self.state.sessions.values_mut() {
+            if session.user_id == user_id && session.terminated_at.is_none() {
+                session.terminated_at = Some(now);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HealthRepository for MemoryUnitOfWork {
+    async fn ping(&mut self) -> Result<(), BankError> {
+        Ok(())
+    }
+
+    // El estado inicial equivale a tener todas las migraciones aplicadas
+    async fn latest_migration_version(&mut self) -> Result<Option<i64>, BankError> {
+        Ok(crate::MIGRATOR.iter().map(|migration| migration.version).max())
+    }
+
+    async fn singleton_row_counts(&mut self) -> Result<SingletonRowCounts, BankError> {
+        Ok(SingletonRowCounts {
+            total_supply: 1,
+            transaction_count: 1,
+        })
+    }
+}
@@ -0,0 +1,323 @@
+// src/repository/mod.rs
+
+pub mod memory;
+pub mod mysql;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::errors::BankError;
+use crate::models::{
+    Account, AccountBalance, AuditEntry, AuthEvent, AuthEventRecord, EntryKind, HistoryEntry,
+    HistoryQuery, IdempotencyRecord, InviteCode, LoginChallenge, MismatchedAccount, PasswordReset,
+    Posting, RefreshToken, Session, SingletonRowCounts, ThrottleScope, TotpSecret, UnbalancedEntry,
+    User, UserSummary,
+};
+
+// Punto de entrada del almacenamiento. Todo acceso pasa por una unidad de trabajo:
+// lo que no se confirma con `commit` se descarta.
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, BankError>;
+
+    // Estado del pool de conexiones, si el almacenamiento usa uno
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+}
+
+#[async_trait]
+pub trait UnitOfWork:
+    UserRepository
+    + AccountRepository
+    + TransactionRepository
+    + AuditRepository
+    + TokenRepository
+    + InviteRepository
+    + PasswordResetRepository
+    + TwoFactorRepository
+    + LoginThrottleRepository
+    + SessionRepository
+    + HealthRepository
+    + Send
+{
+    async fn commit(self: Box<Self>) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait UserRepository {
+    async fn find_user_by_username(&mut self, username: &str) -> Result<Option<User>, BankError>;
+
+    async fn find_user_by_id(&mut self, user_id: i32) -> Result<Option<User>, BankError>;
+
+    // Bloquea la fila del usuario hasta el final de la unidad de trabajo para serializar
+    // las operaciones que comprueban un límite por usuario antes de insertar
+    async fn lock_user(&mut self, user_id: i32) -> Result<(), BankError>;
+
+    // Devuelve `UsernameTaken` si el nombre ya existe
+    async fn insert_user(&mut self, username: &str, password_hash: &str, role: &str) -> Result<i32, BankError>;
+
+    async fn list_users(&mut self) -> Result<Vec<UserSummary>, BankError>;
+
+    async fn update_user_role(&mut self, user_id: i32, role: &str) -> Result<(), BankError>;
+
+    async fn set_user_disabled(&mut self, user_id: i32, disabled: bool) -> Result<(), BankError>;
+
+    async fn update_password_hash(&mut self, user_id: i32, password_hash: &str) -> Result<(), BankError>;
+
+    // Invalida todos los access tokens emitidos hasta ahora para el usuario
+    async fn increment_token_version(&mut self, user_id: i32) -> Result<(), BankError>;
+
+    // Nombres de los permisos asignados al rol en `role_permissions`
+    async fn find_role_permissions(&mut self, role: &str) -> Result<Vec<String>, BankError>;
+}
+
+#[async_trait]
+pub trait AccountRepository {
+    async fn insert_account(&mut self, user_id: i32) -> Result<i32, BankError>;
+
+    async fn find_account_by_user(&mut self, user_id: i32) -> Result<Option<AccountBalance>, BankError>;
+
+    // Solo cuentas de clientes; la reserva del banco nunca se expone
+    async fn find_customer_account(&mut self, account_id: i32) -> Result<Option<AccountBalance>, BankError>;
+
+    // Bloquea las cuentas de los usuarios hasta el final de la unidad de trabajo, una a una
+    // en orden ascendente de id de cuenta para no provocar deadlocks. Las devuelve en ese orden.
+    async fn lock_accounts_by_users(&mut self, user_ids: &[i32]) -> Result<Vec<Account>, BankError>;
+
+    async fn reserve_account_id(&mut self) -> Result<i32, BankError>;
+
+    async fn reserve_balance(&mut self) -> Result<Decimal, BankError>;
+
+    // Cuentas cuyo saldo cacheado no coincide con la suma de sus apuntes
+    async fn mismatched_accounts(&mut self) -> Result<Vec<MismatchedAccount>, BankError>;
+}
+
+#[async_trait]
+pub trait TransactionRepository {
+    async fn insert_transaction(
+        &mut self,
+        sender_id: i32,
+        recipient_id: i32,
+        amount: Decimal,
+    ) -> Result<i32, BankError>;
+
+    async fn list_transactions(
+        &mut self,
+        user_id: i32,
+        query: &HistoryQuery,
+        limit: u32,
+    ) -> Result<Vec<HistoryEntry>, BankError>;
+
+    // Guarda el asiento con sus apuntes y aplica cada apunte al saldo de su cuenta
+    async fn insert_journal_entry(
+        &mut self,
+        kind: EntryKind,
+        transaction_id: i32,
+        postings: &[Posting],
+    ) -> Result<i32, BankError>;
+
+    async fn unbalanced_entries(&mut self) -> Result<Vec<UnbalancedEntry>, BankError>;
+
+    // La fila queda bloqueada hasta el final de la unidad de trabajo
+    async fn find_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, BankError>;
+
+    async fn insert_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait AuditRepository {
+    async fn insert_audit_log(&mut self, entry: &AuditEntry) -> Result<(), BankError>;
+
+    async fn adjust_total_supply(&mut self, delta: Decimal) -> Result<(), BankError>;
+
+    async fn total_supply(&mut self) -> Result<Decimal, BankError>;
+
+    async fn increment_transaction_count(&mut self) -> Result<(), BankError>;
+
+    async fn insert_auth_event(&mut self, event: &AuthEvent) -> Result<(), BankError>;
+
+    // Los más recientes primero
+    async fn list_auth_events(&mut self, limit: u32) -> Result<Vec<AuthEventRecord>, BankError>;
+}
+
+#[async_trait]
+pub trait TokenRepository {
+    async fn insert_refresh_token(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        family_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError>;
+
+    // La fila queda bloqueada hasta el final de la unidad de trabajo
+    async fn find_refresh_token(&mut self, token_hash: &str) -> Result<Option<RefreshToken>, BankError>;
+
+    async fn revoke_refresh_token(&mut self, refresh_token_id: i32) -> Result<(), BankError>;
+
+    async fn revoke_refresh_token_family(&mut self, family_id: &str) -> Result<(), BankError>;
+
+    // Revoca todos los refresh tokens vigentes del usuario
+    async fn revoke_user_refresh_tokens(&mut self, user_id: i32) -> Result<(), BankError>;
+
+    async fn revoke_access_token(&mut self, jti: &str, expires_at: DateTime<Utc>) -> Result<(), BankError>;
+
+    async fn is_access_token_revoked(&mut self, jti: &str) -> Result<bool, BankError>;
+
+    // Borra las revocaciones cuyo access token ya expiró por sí solo
+    async fn delete_expired_revoked_tokens(&mut self, now: DateTime<Utc>) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait InviteRepository {
+    async fn insert_invite_code(
+        &mut self,
+        code: &str,
+        created_by: i32,
+        expires_at: DateTime<Utc>,
+    ) -> Result<InviteCode, BankError>;
+
+    // La fila queda bloqueada para que dos registros no usen el mismo código
+    async fn find_invite_code(&mut self, code: &str) -> Result<Option<InviteCode>, BankError>;
+
+    async fn mark_invite_code_used(&mut self, invite_code_id: i32, used_by: i32) -> Result<(), BankError>;
+
+    async fn list_invite_codes(&mut self, created_by: i32) -> Result<Vec<InviteCode>, BankError>;
+}
+
+#[async_trait]
+pub trait PasswordResetRepository {
+    async fn insert_password_reset(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError>;
+
+    // La fila queda bloqueada para que el token no se use dos veces
+    async fn find_password_reset(&mut self, token_hash: &str) -> Result<Option<PasswordReset>, BankError>;
+
+    // Marca como usados todos los tokens pendientes del usuario
+    async fn consume_password_resets(&mut self, user_id: i32) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait TwoFactorRepository {
+    // La fila queda bloqueada para que un mismo código no se acepte dos veces
+    async fn find_totp_secret(&mut self, user_id: i32) -> Result<Option<TotpSecret>, BankError>;
+
+    // Consulta sin bloqueo, para la comprobación que se hace en cada petición
+    async fn is_totp_enabled(&mut self, user_id: i32) -> Result<bool, BankError>;
+
+    // Guarda un secreto nuevo sin activar, reemplazando uno pendiente si lo hubiera
+    async fn save_pending_totp_secret(&mut self, user_id: i32, secret: &str) -> Result<(), BankError>;
+
+    async fn enable_totp(&mut self, user_id: i32) -> Result<(), BankError>;
+
+    async fn update_totp_last_used_step(&mut self, user_id: i32, step: i64) -> Result<(), BankError>;
+
+    // Borra el secreto y los códigos de recuperación
+    async fn delete_totp(&mut self, user_id: i32) -> Result<(), BankError>;
+
+    async fn replace_recovery_codes(&mut self, user_id: i32, code_hashes: &[String]) -> Result<(), BankError>;
+
+    // Marca el código como usado; devuelve `false` si no existe o ya se usó
+    async fn use_recovery_code(&mut self, user_id: i32, code_hash: &str) -> Result<bool, BankError>;
+
+    async fn insert_login_challenge(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError>;
+
+    async fn find_login_challenge(&mut self, token_hash: &str) -> Result<Option<LoginChallenge>, BankError>;
+
+    async fn record_login_challenge_failure(&mut self, challenge_id: i32) -> Result<(), BankError>;
+
+    async fn consume_login_challenge(&mut self, challenge_id: i32) -> Result<(), BankError>;
+
+    async fn role_requires_two_factor(&mut self, role: &str) -> Result<bool, BankError>;
+
+    async fn set_role_requires_two_factor(&mut self, role: &str, required: bool) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait LoginThrottleRepository {
+    // Hasta cuándo está bloqueado el sujeto, si lo está o lo estuvo
+    async fn find_login_lock(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+    ) -> Result<Option<DateTime<Utc>>, BankError>;
+
+    // Suma un fallo y devuelve el total; los fallos anteriores a `window_start` no cuentan
+    async fn record_login_failure(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+    ) -> Result<i32, BankError>;
+
+    async fn lock_login_subject(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        until: DateTime<Utc>,
+    ) -> Result<(), BankError>;
+
+    async fn clear_login_attempts(&mut self, scope: ThrottleScope, subject: &str) -> Result<(), BankError>;
+}
+
+#[async_trait]
+pub trait SessionRepository {
+    async fn insert_session(
+        &mut self,
+        session_id: &str,
+        user_id: i32,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<(), BankError>;
+
+    async fn find_session(&mut self, session_id: &str) -> Result<Option<Session>, BankError>;
+
+    // Sesiones sin cerrar, la de actividad más reciente primero
+    async fn list_active_sessions(&mut self, user_id: i32) -> Result<Vec<Session>, BankError>;
+
+    async fn touch_session(&mut self, session_id: &str, last_seen_at: DateTime<Utc>) -> Result<(), BankError>;
+
+    async fn terminate_session(&mut self, session_id: &str) -> Result<(), BankError>;
+
+    // Cierra todas las sesiones abiertas del usuario
+    async fn terminate_user_sessions(&mut self, user_id: i32) -> Result<(), BankError>;
+}
+
+// Consultas de la sonda de disponibilidad; no modifican nada
+#[async_trait]
+pub trait HealthRepository {
+    async fn ping(&mut self) -> Result<(), BankError>;
+
+    // Versión de la última migración aplicada con éxito
+    async fn latest_migration_version(&mut self) -> Result<Option<i64>, BankError>;
+
+    async fn singleton_row_counts(&mut self) -> Result<SingletonRowCounts, BankError>;
+}
@@ -0,0 +1,1082 @@
+// src/repository/mysql.rs
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{MySql, Pool, Transaction};
+use tracing::Instrument;
+
+use crate::errors::BankError;
+use crate::models::{
+    Account, AccountBalance, AuditEntry, AuthEvent, AuthEventRecord, EntryKind, HistoryEntry,
+    HistoryQuery, IdempotencyRecord, InviteCode, LoginChallenge, MismatchedAccount, PasswordReset,
+    Posting, RefreshToken, Session, SingletonRowCounts, ThrottleScope, TotpSecret, UnbalancedEntry,
+    User, UserSummary,
+};
+use crate::repository::{
+    AccountRepository, AuditRepository, HealthRepository, InviteRepository,
+    LoginThrottleRepository, PasswordResetRepository, PoolStats, SessionRepository, Store,
+    TokenRepository, TransactionRepository, TwoFactorRepository, UnitOfWork, UserRepository,
+};
+
+#[derive(Clone)]
+pub struct MySqlStore {
+    pool: Pool<MySql>,
+}
+
+impl MySqlStore {
+    pub fn new(pool: Pool<MySql>) -> MySqlStore {
+        MySqlStore { pool }
+    }
+}
+
+#[async_trait]
+impl Store for MySqlStore {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, BankError> {
+        let transaction = self.pool.begin().instrument(tracing::debug_span!("db.begin")).await?;
+        Ok(Box::new(MySqlUnitOfWork { transaction }))
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max_connections: self.pool.options().get_max_connections(),
+        })
+    }
+}
+
+// Una transacción de MySQL; si se suelta sin `commit`, sqlx la revierte
+pub struct MySqlUnitOfWork {
+    transaction: Transaction<'static, MySql>,
+}
+
+#[async_trait]
+impl UnitOfWork for MySqlUnitOfWork {
+    async fn commit(self: Box<Self>) -> Result<(), BankError> {
+        self.transaction.commit().instrument(tracing::debug_span!("db.commit")).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepository for MySqlUnitOfWork {
+    async fn find_user_by_username(&mut self, username: &str) -> Result<Option<User>, BankError> {
+        let user = sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash, role, disabled, token_version
+            FROM users WHERE username = ?"
+        )
+        .bind(username)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn find_user_by_id(&mut self, user_id: i32) -> Result<Option<User>, BankError> {
+        let user = sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash, role, disabled, token_version
+            FROM users WHERE id = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(user)
+    }
+
+    async fn lock_user(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query("SELECT id FROM users WHERE id = ? FOR UPDATE")
+            .bind(user_id)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert_user(&mut self, username: &str, password_hash: &str, role: &str) -> Result<i32, BankError> {
+        let user_id = sqlx::query(
+            "INSERT INTO users (username, password_hash, role) VALUES (?, ?, ?)"
+        )
+        .bind(username)
+        .bind(password_hash)
+        .bind(role)
+        .execute(&mut *self.transaction)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_error) if db_error.is_unique_violation() => BankError::UsernameTaken,
+            e => BankError::from(e),
+        })?
+        .last_insert_id() as i32;
+
+        Ok(user_id)
+    }
+
+    async fn list_users(&mut self) -> Result<Vec<UserSummary>, BankError> {
+        let users = sqlx::query_as::<_, UserSummary>(
+            "SELECT id, username, role, disabled FROM users ORDER BY id"
+        )
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(users)
+    }
+
+    async fn update_user_role(&mut self, user_id: i32, role: &str) -> Result<(), BankError> {
+        sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+            .bind(role)
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_user_disabled(&mut self, user_id: i32, disabled: bool) -> Result<(), BankError> {
+        sqlx::query("UPDATE users SET disabled = ? WHERE id = ?")
+            .bind(disabled)
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_password_hash(&mut self, user_id: i32, password_hash: &str) -> Result<(), BankError> {
+        sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+            .bind(password_hash)
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn increment_token_version(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query("UPDATE users SET token_version = token_version + 1 WHERE id = ?")
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_role_permissions(&mut self, role: &str) -> Result<Vec<String>, BankError> {
+        let permissions = sqlx::query_scalar::<_, String>(
+            "SELECT permission FROM role_permissions WHERE role = ?"
+        )
+        .bind(role)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(permissions)
+    }
+}
+
+#[async_trait]
+impl AccountRepository for MySqlUnitOfWork {
+    async fn insert_account(&mut self, user_id: i32) -> Result<i32, BankError> {
+        let account_id = sqlx::query(
+            "INSERT INTO accounts (user_id, balance) VALUES (?, 0)"
+        )
+        .bind(user_id)
+        .execute(&mut *self.transaction)
+        .await?
+        .last_insert_id() as i32;
+
+        Ok(account_id)
+    }
+
+    async fn find_account_by_user(&mut self, user_id: i32) -> Result<Option<AccountBalance>, BankError> {
+        let account = sqlx::query_as::<_, AccountBalance>(
+            "SELECT id, user_id, balance, updated_at FROM accounts WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(account)
+    }
+
+    async fn find_customer_account(&mut self, account_id: i32) -> Result<Option<AccountBalance>, BankError> {
+        let account = sqlx::query_as::<_, AccountBalance>(
+            "SELECT id, user_id, balance, updated_at FROM accounts WHERE id = ? AND kind = 'customer'"
+        )
+        .bind(account_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(account)
+    }
+
+    async fn lock_accounts_by_users(&mut self, user_ids: &[i32]) -> Result<Vec<Account>, BankError> {
+        // Un `IN (...) ORDER BY id FOR UPDATE` bloquea las filas según recorre el índice de
+        // user_id, no en el orden del ORDER BY. Primero se buscan los ids de cuenta, que no
+        // cambian, y después se bloquea cada cuenta por su clave primaria, de menor a mayor.
+        let mut query = sqlx::QueryBuilder::<MySql>::new("SELECT id FROM accounts WHERE user_id IN (");
+        let mut separated = query.separated(", ");
+        for user_id in user_ids {
+            separated.push_bind(*user_id);
+        }
+        query.push(")");
+
+        let mut account_ids = query
+            .build_query_scalar::<i32>()
+            .fetch_all(&mut *self.transaction)
+            .await?;
+        account_ids.sort_unstable();
+
+        let mut accounts = Vec::with_capacity(account_ids.len());
+        for account_id in account_ids {
+            let account = sqlx::query_as::<_, Account>(
+                "SELECT id, user_id, balance FROM accounts WHERE id = ? FOR UPDATE"
+            )
+            .bind(account_id)
+            .fetch_one(&mut *self.transaction)
+            .await?;
+            accounts.push(account);
+        }
+
+        Ok(accounts)
+    }
+
+    async fn reserve_account_id(&mut self) -> Result<i32, BankError> {
+        let reserve_id = sqlx::query_scalar::<_, i32>("SELECT id FROM accounts WHERE kind = 'reserve'")
+            .fetch_one(&mut *self.transaction)
+            .await?;
+
+        Ok(reserve_id)
+    }
+
+    async fn reserve_balance(&mut self) -> Result<Decimal, BankError> {
+        let balance = sqlx::query_scalar::<_, Decimal>("SELECT balance FROM accounts WHERE kind = 'reserve'")
+            .fetch_one(&mut *self.transaction)
+            .await?;
+
+        Ok(balance)
+    }
+
+    async fn mismatched_accounts(&mut self) -> Result<Vec<MismatchedAccount>, BankError> {
+        let accounts = sqlx::query_as::<_, MismatchedAccount>(
+            r#"SELECT a.id AS account_id, a.balance, COALESCE(SUM(p.amount), 0) AS derived_balance
+            FROM accounts a
+            LEFT JOIN postings p ON p.account_id = a.id
+            GROUP BY a.id, a.balance
+            HAVING a.balance <> COALESCE(SUM(p.amount), 0)"#
+        )
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(accounts)
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for MySqlUnitOfWork {
+    async fn insert_transaction(
+        &mut self,
+        sender_id: i32,
+        recipient_id: i32,
+        amount: Decimal,
+    ) -> Result<i32, BankError> {
+        let transaction_id = sqlx::query(
+            "INSERT INTO transactions (sender_id, recipient_id, amount) VALUES (?, ?, ?)"
+        )
+        .bind(sender_id)
+        .bind(recipient_id)
+        .bind(amount)
+        .execute(&mut *self.transaction)
+        .await?
+        .last_insert_id() as i32;
+
+        Ok(transaction_id)
+    }
+
+    async fn list_transactions(
+        &mut self,
+        user_id: i32,
+        query: &HistoryQuery,
+        limit: u32,
+    ) -> Result<Vec<HistoryEntry>, BankError> {
+        let direction = query.direction.as_ref().map(|d| d.as_str());
+
+        let entries = sqlx::query_as::<_, HistoryEntry>(
+            r#"SELECT t.id, t.amount, t.created_at,
+                CASE WHEN t.sender_id = ? THEN 'out' ELSE 'in' END AS direction,
+                u.username AS counterparty
+            FROM transactions t
+            JOIN users u ON u.id = CASE WHEN t.sender_id = ? THEN t.recipient_id ELSE t.sender_id END
+            WHERE (t.sender_id = ? OR t.recipient_id = ?)
+                AND (? IS NULL OR t.id < ?)
+                AND (? IS NULL OR t.created_at >= ?)
+                AND (? IS NULL OR t.created_at < ?)
+                AND (? IS NULL OR (? = 'out' AND t.sender_id = ?) OR (? = 'in' AND t.recipient_id = ?))
+                AND (? IS NULL OR u.username = ?)
+                AND (? IS NULL OR t.amount >= ?)
+                AND (? IS NULL OR t.amount <= ?)
+            ORDER BY t.id DESC
+            LIMIT ?"#
+        )
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(query.cursor)
+        .bind(query.cursor)
+        .bind(query.from)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.to)
+        .bind(direction)
+        .bind(direction)
+        .bind(user_id)
+        .bind(direction)
+        .bind(user_id)
+        .bind(&query.counterparty)
+        .bind(&query.counterparty)
+        .bind(query.min_amount)
+        .bind(query.min_amount)
+        .bind(query.max_amount)
+        .bind(query.max_amount)
+        .bind(limit)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn insert_journal_entry(
+        &mut self,
+        kind: EntryKind,
+        transaction_id: i32,
+        postings: &[Posting],
+    ) -> Result<i32, BankError> {
+        let journal_entry_id = sqlx::query(
+            "INSERT INTO journal_entries (kind, transaction_id) VALUES (?, ?)"
+        )
+        .bind(kind.as_str())
+        .bind(transaction_id)
+        .execute(&mut *self.transaction)
+        .await?
+        .last_insert_id() as i32;
+
+        for posting in postings {
+            sqlx::query(
+                "INSERT INTO postings (journal_entry_id, account_id, amount) VALUES (?, ?, ?)"
+            )
+            .bind(journal_entry_id)
+            .bind(posting.account_id)
+            .bind(posting.amount)
+            .execute(&mut *self.transaction)
+            .await?;
+
+            sqlx::query(
+                "UPDATE accounts SET balance = balance + ? WHERE id = ?"
+            )
+            .bind(posting.amount)
+            .bind(posting.account_id)
+            .execute(&mut *self.transaction)
+            .await?;
+        }
+
+        Ok(journal_entry_id)
+    }
+
+    async fn unbalanced_entries(&mut self) -> Result<Vec<UnbalancedEntry>, BankError> {
+        let entries = sqlx::query_as::<_, UnbalancedEntry>(
+            r#"SELECT journal_entry_id, SUM(amount) AS total
+            FROM postings
+            GROUP BY journal_entry_id
+            HAVING SUM(amount) <> 0"#
+        )
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(entries)
+    }
+
+    async fn find_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+    ) -> Result<Option<IdempotencyRecord>, BankError> {
+        let record = sqlx::query_as::<_, IdempotencyRecord>(
+            "SELECT request_hash, response_status, response_body FROM idempotency_keys
+            WHERE user_id = ? AND idempotency_key = ? FOR UPDATE"
+        )
+        .bind(user_id)
+        .bind(key)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn insert_idempotency_record(
+        &mut self,
+        user_id: i32,
+        key: &str,
+        record: &IdempotencyRecord,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO idempotency_keys (user_id, idempotency_key, request_hash, response_status, response_body)
+            VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(key)
+        .bind(&record.request_hash)
+        .bind(record.response_status)
+        .bind(&record.response_body)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MySqlUnitOfWork {
+    async fn insert_audit_log(&mut self, entry: &AuditEntry) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO audit_log (action, actor_user_id, target_user_id, amount, details)
+            VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(entry.action.as_str())
+        .bind(entry.actor_user_id)
+        .bind(entry.target_user_id)
+        .bind(entry.amount)
+        .bind(&entry.details)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn adjust_total_supply(&mut self, delta: Decimal) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE total_supply SET total_amount = total_amount + ?"
+        )
+        .bind(delta)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn total_supply(&mut self) -> Result<Decimal, BankError> {
+        let supply = sqlx::query_scalar::<_, Decimal>("SELECT total_amount FROM total_supply")
+            .fetch_one(&mut *self.transaction)
+            .await?;
+
+        Ok(supply)
+    }
+
+    async fn increment_transaction_count(&mut self) -> Result<(), BankError> {
+        sqlx::query("UPDATE transaction_count SET count = count + 1")
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert_auth_event(&mut self, event: &AuthEvent) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO auth_audit_log (event, username, user_id, ip_address, details)
+            VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(event.kind.as_str())
+        .bind(&event.username)
+        .bind(event.user_id)
+        .bind(&event.ip_address)
+        .bind(&event.details)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_auth_events(&mut self, limit: u32) -> Result<Vec<AuthEventRecord>, BankError> {
+        let events = sqlx::query_as::<_, AuthEventRecord>(
+            "SELECT id, event, username, user_id, ip_address, details, created_at
+            FROM auth_audit_log ORDER BY id DESC LIMIT ?"
+        )
+        .bind(limit)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl TokenRepository for MySqlUnitOfWork {
+    async fn insert_refresh_token(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        family_id: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO refresh_tokens (user_id, token_hash, family_id, expires_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(family_id)
+        .bind(expires_at)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_refresh_token(&mut self, token_hash: &str) -> Result<Option<RefreshToken>, BankError> {
+        let token = sqlx::query_as::<_, RefreshToken>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at FROM refresh_tokens
+            WHERE token_hash = ? FOR UPDATE"
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(token)
+    }
+
+    async fn revoke_refresh_token(&mut self, refresh_token_id: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = CURRENT_TIMESTAMP WHERE id = ? AND revoked_at IS NULL"
+        )
+        .bind(refresh_token_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_refresh_token_family(&mut self, family_id: &str) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = CURRENT_TIMESTAMP WHERE family_id = ? AND revoked_at IS NULL"
+        )
+        .bind(family_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_user_refresh_tokens(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = CURRENT_TIMESTAMP WHERE user_id = ? AND revoked_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_access_token(&mut self, jti: &str, expires_at: DateTime<Utc>) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT IGNORE INTO revoked_tokens (jti, expires_at) VALUES (?, ?)"
+        )
+        .bind(jti)
+        .bind(expires_at)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_access_token_revoked(&mut self, jti: &str) -> Result<bool, BankError> {
+        let revoked = sqlx::query("SELECT jti FROM revoked_tokens WHERE jti = ?")
+            .bind(jti)
+            .fetch_optional(&mut *self.transaction)
+            .await?;
+
+        Ok(revoked.is_some())
+    }
+
+    async fn delete_expired_revoked_tokens(&mut self, now: DateTime<Utc>) -> Result<(), BankError> {
+        sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < ?")
+            .bind(now)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl InviteRepository for MySqlUnitOfWork {
+    async fn insert_invite_code(
+        &mut self,
+        code: &str,
+        created_by: i32,
+        expires_at: DateTime<Utc>,
+    ) -> Result<InviteCode, BankError> {
+        let id = sqlx::query(
+            "INSERT INTO invite_codes (code, created_by, expires_at) VALUES (?, ?, ?)"
+        )
+        .bind(code)
+        .bind(created_by)
+        .bind(expires_at)
+        .execute(&mut *self.transaction)
+        .await?
+        .last_insert_id() as i32;
+
+        Ok(InviteCode {
+            id,
+            code: code.to_string(),
+            created_by,
+            used_by: None,
+            expires_at,
+            used_at: None,
+        })
+    }
+
+    async fn find_invite_code(&mut self, code: &str) -> Result<Option<InviteCode>, BankError> {
+        let invite_code = sqlx::query_as::<_, InviteCode>(
+            "SELECT id, code, created_by, used_by, expires_at, used_at FROM invite_codes
+            WHERE code = ? FOR UPDATE"
+        )
+        .bind(code)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(invite_code)
+    }
+
+    async fn mark_invite_code_used(&mut self, invite_code_id: i32, used_by: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE invite_codes SET used_by = ?, used_at = CURRENT_TIMESTAMP WHERE id = ?"
+        )
+        .bind(used_by)
+        .bind(invite_code_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_invite_codes(&mut self, created_by: i32) -> Result<Vec<InviteCode>, BankError> {
+        let invite_codes = sqlx::query_as::<_, InviteCode>(
+            "SELECT id, code, created_by, used_by, expires_at, used_at FROM invite_codes
+            WHERE created_by = ? ORDER BY id DESC"
+        )
+        .bind(created_by)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(invite_codes)
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for MySqlUnitOfWork {
+    async fn insert_password_reset(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES (?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_password_reset(&mut self, token_hash: &str) -> Result<Option<PasswordReset>, BankError> {
+        let password_reset = sqlx::query_as::<_, PasswordReset>(
+            "SELECT id, user_id, expires_at, used_at FROM password_resets WHERE token_hash = ? FOR UPDATE"
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(password_reset)
+    }
+
+    async fn consume_password_resets(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE password_resets SET used_at = CURRENT_TIMESTAMP WHERE user_id = ? AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactorRepository for MySqlUnitOfWork {
+    async fn find_totp_secret(&mut self, user_id: i32) -> Result<Option<TotpSecret>, BankError> {
+        let totp_secret = sqlx::query_as::<_, TotpSecret>(
+            "SELECT user_id, secret, enabled_at, last_used_step FROM user_totp WHERE user_id = ? FOR UPDATE"
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(totp_secret)
+    }
+
+    async fn is_totp_enabled(&mut self, user_id: i32) -> Result<bool, BankError> {
+        let enabled = sqlx::query(
+            "SELECT user_id FROM user_totp WHERE user_id = ? AND enabled_at IS NOT NULL"
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(enabled.is_some())
+    }
+
+    async fn save_pending_totp_secret(&mut self, user_id: i32, secret: &str) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO user_totp (user_id, secret) VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE secret = VALUES(secret), enabled_at = NULL, last_used_step = NULL"
+        )
+        .bind(user_id)
+        .bind(secret)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn enable_totp(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query("UPDATE user_totp SET enabled_at = CURRENT_TIMESTAMP WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_totp_last_used_step(&mut self, user_id: i32, step: i64) -> Result<(), BankError> {
+        sqlx::query("UPDATE user_totp SET last_used_step = ? WHERE user_id = ?")
+            .bind(step)
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_totp(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        sqlx::query("DELETE FROM user_totp WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn replace_recovery_codes(&mut self, user_id: i32, code_hashes: &[String]) -> Result<(), BankError> {
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        if code_hashes.is_empty() {
+            return Ok(());
+        }
+
+        let mut query = sqlx::QueryBuilder::<MySql>::new("INSERT INTO recovery_codes (user_id, code_hash) ");
+        query.push_values(code_hashes, |mut row, code_hash| {
+            row.push_bind(user_id).push_bind(code_hash);
+        });
+        query.build().execute(&mut *self.transaction).await?;
+
+        Ok(())
+    }
+
+    async fn use_recovery_code(&mut self, user_id: i32, code_hash: &str) -> Result<bool, BankError> {
+        let result = sqlx::query(
+            "UPDATE recovery_codes SET used_at = CURRENT_TIMESTAMP
+            WHERE user_id = ? AND code_hash = ? AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn insert_login_challenge(
+        &mut self,
+        user_id: i32,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO login_challenges (user_id, token_hash, expires_at) VALUES (?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_login_challenge(&mut self, token_hash: &str) -> Result<Option<LoginChallenge>, BankError> {
+        let challenge = sqlx::query_as::<_, LoginChallenge>(
+            "SELECT id, user_id, expires_at, failed_attempts, used_at FROM login_challenges
+            WHERE token_hash = ? FOR UPDATE"
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(challenge)
+    }
+
+    async fn record_login_challenge_failure(&mut self, challenge_id: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE login_challenges SET failed_attempts = failed_attempts + 1 WHERE id = ?"
+        )
+        .bind(challenge_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn consume_login_challenge(&mut self, challenge_id: i32) -> Result<(), BankError> {
+        sqlx::query("UPDATE login_challenges SET used_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(challenge_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn role_requires_two_factor(&mut self, role: &str) -> Result<bool, BankError> {
+        let required = sqlx::query_scalar::<_, bool>(
+            "SELECT require_two_factor FROM role_policies WHERE role = ?"
+        )
+        .bind(role)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(required.unwrap_or(false))
+    }
+
+    async fn set_role_requires_two_factor(&mut self, role: &str, required: bool) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO role_policies (role, require_two_factor) VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE require_two_factor = VALUES(require_two_factor)"
+        )
+        .bind(role)
+        .bind(required)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoginThrottleRepository for MySqlUnitOfWork {
+    async fn find_login_lock(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+    ) -> Result<Option<DateTime<Utc>>, BankError> {
+        let locked_until = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT locked_until FROM login_attempts WHERE scope = ? AND subject = ?"
+        )
+        .bind(scope.as_str())
+        .bind(subject)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(locked_until.flatten())
+    }
+
+    async fn record_login_failure(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        now: DateTime<Utc>,
+        window_start: DateTime<Utc>,
+    ) -> Result<i32, BankError> {
+        // `failures` se evalúa antes que `last_failure_at`, así compara con el fallo anterior
+        sqlx::query(
+            "INSERT INTO login_attempts (scope, subject, failures, last_failure_at) VALUES (?, ?, 1, ?)
+            ON DUPLICATE KEY UPDATE
+                failures = IF(last_failure_at < ?, 1, failures + 1),
+                last_failure_at = VALUES(last_failure_at)"
+        )
+        .bind(scope.as_str())
+        .bind(subject)
+        .bind(now)
+        .bind(window_start)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        let failures = sqlx::query_scalar::<_, i32>(
+            "SELECT failures FROM login_attempts WHERE scope = ? AND subject = ? FOR UPDATE"
+        )
+        .bind(scope.as_str())
+        .bind(subject)
+        .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(failures)
+    }
+
+    async fn lock_login_subject(
+        &mut self,
+        scope: ThrottleScope,
+        subject: &str,
+        until: DateTime<Utc>,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE login_attempts SET locked_until = ? WHERE scope = ? AND subject = ?"
+        )
+        .bind(until)
+        .bind(scope.as_str())
+        .bind(subject)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear_login_attempts(&mut self, scope: ThrottleScope, subject: &str) -> Result<(), BankError> {
+        sqlx::query(
+            "DELETE FROM login_attempts WHERE scope = ? AND subject = ?"
+        )
+        .bind(scope.as_str())
+        .bind(subject)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MySqlUnitOfWork {
+    async fn insert_session(
+        &mut self,
+        session_id: &str,
+        user_id: i32,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<(), BankError> {
+        sqlx::query(
+            "INSERT INTO sessions (id, user_id, user_agent, ip_address) VALUES (?, ?, ?, ?)"
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(user_agent)
+        .bind(ip_address)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_session(&mut self, session_id: &str) -> Result<Option<Session>, BankError> {
+        let session = sqlx::query_as::<_, Session>(
+            "SELECT id, user_id, user_agent, ip_address, created_at, last_seen_at, terminated_at
+            FROM sessions WHERE id = ?"
+        )
+        .bind(session_id)
+        .fetch_optional(&mut *self.transaction)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn list_active_sessions(&mut self, user_id: i32) -> Result<Vec<Session>, BankError> {
+        let sessions = sqlx::query_as::<_, Session>(
+            "SELECT id, user_id, user_agent, ip_address, created_at, last_seen_at, terminated_at
+            FROM sessions WHERE user_id = ? AND terminated_at IS NULL ORDER BY last_seen_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&mut *self.transaction)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    async fn touch_session(&mut self, session_id: &str, last_seen_at: DateTime<Utc>) -> Result<(), BankError> {
+        sqlx::query("UPDATE sessions SET last_seen_at = ? WHERE id = ?")
+            .bind(last_seen_at)
+            .bind(session_id)
+            .execute(&mut *self.transaction)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn terminate_session(&mut self, session_id: &str) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE sessions SET terminated_at = CURRENT_TIMESTAMP WHERE id = ? AND terminated_at IS NULL"
+        )
+        .bind(session_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn terminate_user_sessions(&mut self, user_id: i32) -> Result<(), BankError> {
+        sqlx::query(
+            "UPDATE sessions SET terminated_at = CURRENT_TIMESTAMP WHERE user_id = ? AND terminated_at IS NULL"
+        )
+        .bind(user_id)
+        .execute(&mut *self.transaction)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HealthRepository for MySqlUnitOfWork {
+    async fn ping(&mut self) -> Result<(), BankError> {
+        sqlx::query("SELECT 1").execute(&mut *self.transaction).await?;
+        Ok(())
+    }
+
+    async fn latest_migration_version(&mut self) -> Result<Option<i64>, BankError> {
+        let latest = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(version) FROM _sqlx_migrations WHERE success = TRUE"
+        )
+        .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(latest)
+    }
+
+    async fn singleton_row_counts(&mut self) -> Result<SingletonRowCounts, BankError> {
+        let counts = sqlx::query_as::<_, SingletonRowCounts>(
+            "SELECT
+                (SELECT COUNT(*) FROM total_supply) AS total_supply,
+                (SELECT COUNT(*) FROM transaction_count) AS transaction_count"
+        )
+        .fetch_one(&mut *self.transaction)
+        .await?;
+
+        Ok(counts)
+    }
+}
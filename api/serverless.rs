@@ -0,0 +1,197 @@
+// src/serverless.rs
+
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::thread;
+
+use actix_service::IntoServiceFactory;
+use actix_web::body::{self, MessageBody};
+use actix_web::dev::{AppConfig, Payload, Service, ServiceFactory};
+use actix_web::http::header as actix_header;
+use actix_web::http::{Method, Uri};
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use tokio::sync::{mpsc, oneshot};
+use vercel_runtime::{Body, Error, Request, Response};
+
+use crate::config::Config;
+use crate::{build_app, logging, pool_options, AppState};
+
+// IP del cliente según el proxy de Vercel, que sobrescribe la que mande el propio cliente
+pub const CLIENT_IP_HEADER: &str = "x-real-ip";
+
+// Petición copiada a tipos `Send` para cruzar al hilo de actix
+struct ForwardedRequest {
+    method: String,
+    uri: String,
+    headers: Vec<(String, Vec<u8>)>,
+    client_ip: Option<IpAddr>,
+    body: Bytes,
+}
+
+struct ForwardedResponse {
+    status: u16,
+    headers: Vec<(String, Vec<u8>)>,
+    body: Bytes,
+}
+
+struct Job {
+    request: ForwardedRequest,
+    reply: oneshot::Sender<Result<ForwardedResponse, String>>,
+}
+
+// La aplicación de `build_app` dentro de una función serverless. Los servicios de actix no
+// son `Send`, así que viven en un hilo propio con su runtime y reciben las peticiones por
+// un canal; los handlers, los middlewares y los servicios son los mismos que en el servidor.
+pub struct ServerlessApp {
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl ServerlessApp {
+    // Arranque en frío: lee la configuración como `deposit`
+    pub fn from_env() -> Result<ServerlessApp, String> {
+        dotenv::dotenv().ok();
+        ServerlessApp::from_config(Config::load()?)
+    }
+
+    // El pool no abre ninguna conexión hasta la primera petición que la necesita. Las
+    // migraciones no se aplican aquí: se lanzan con `deposit migrate` antes de desplegar.
+    pub fn from_config(config: Config) -> Result<ServerlessApp, String> {
+        logging::init(&config.logging);
+
+        let pool = pool_options(&config.database)
+            .connect_lazy(&config.database.url)
+            .map_err(|e| format!("database.url inválida: {}", e))?;
+
+        Ok(ServerlessApp::start(AppState::production(config, pool)?))
+    }
+
+    pub fn start(state: AppState) -> ServerlessApp {
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<Job>();
+
+        thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                let service = match build_app(state).into_factory().new_service(AppConfig::default()).await {
+                    Ok(service) => Rc::new(service),
+                    Err(()) => {
+                        tracing::error!("no se pudo iniciar la aplicación");
+                        return;
+                    }
+                };
+
+                while let Some(job) = receiver.recv().await {
+                    let service = service.clone();
+                    actix_web::rt::spawn(async move {
+                        let response = match job.request.into_actix() {
+                            Ok(request) => match service.call(request).await {
+                                Ok(response) => forward_response(response.into_parts().1).await,
+                                // Lo mismo que hace `HttpServer` con un error que llega hasta arriba
+                                Err(e) => forward_response(e.error_response()).await,
+                            },
+                            Err(e) => forward_response(actix_web::error::ErrorBadRequest(e).error_response()).await,
+                        };
+                        let _ = job.reply.send(response);
+                    });
+                }
+            });
+        });
+
+        ServerlessApp { jobs }
+    }
+
+    pub async fn handle(&self, request: Request) -> Result<Response<Body>, Error> {
+        let (reply, response) = oneshot::channel();
+
+        self.jobs
+            .send(Job { request: ForwardedRequest::from_vercel(request), reply })
+            .map_err(|_| "la aplicación se ha detenido")?;
+
+        let forwarded = response.await.map_err(|_| "la aplicación se ha detenido")??;
+        forwarded.into_vercel()
+    }
+}
+
+impl ForwardedRequest {
+    fn from_vercel(request: Request) -> ForwardedRequest {
+        let (parts, body) = request.into_parts();
+
+        let client_ip = parts
+            .headers
+            .get(CLIENT_IP_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+
+        ForwardedRequest {
+            method: parts.method.as_str().to_string(),
+            // Vercel entrega una URL absoluta; para el enrutado basta la ruta con la query
+            uri: parts
+                .uri
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/")
+                .to_string(),
+            headers: parts
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str().to_string(), value.as_bytes().to_vec()))
+                .collect(),
+            client_ip,
+            body: match body {
+                Body::Empty => Bytes::new(),
+                Body::Text(text) => Bytes::from(text),
+                Body::Binary(bytes) => Bytes::from(bytes),
+            },
+        }
+    }
+
+    fn into_actix(self) -> Result<actix_http::Request, String> {
+        let mut request = actix_http::Request::with_payload(Payload::from(self.body));
+        let head = request.head_mut();
+
+        head.method = Method::from_bytes(self.method.as_bytes()).map_err(|e| e.to_string())?;
+        head.uri = self.uri.parse::<Uri>().map_err(|e| e.to_string())?;
+        for (name, value) in self.headers {
+            head.headers.append(
+                actix_header::HeaderName::from_bytes(name.as_bytes()).map_err(|e| e.to_string())?,
+                actix_header::HeaderValue::from_bytes(&value).map_err(|e| e.to_string())?,
+            );
+        }
+        // El login cuenta los fallos por IP a partir de aquí, como con `HttpServer`
+        head.peer_addr = self.client_ip.map(|ip| SocketAddr::new(ip, 0));
+
+        Ok(request)
+    }
+}
+
+impl ForwardedResponse {
+    fn into_vercel(self) -> Result<Response<Body>, Error> {
+        let body = if self.body.is_empty() { Body::Empty } else { Body::Binary(self.body.to_vec()) };
+        let mut response = Response::new(body);
+
+        *response.status_mut() = http::StatusCode::from_u16(self.status)?;
+        for (name, value) in self.headers {
+            response.headers_mut().append(
+                http::HeaderName::from_bytes(name.as_bytes())?,
+                http::HeaderValue::from_bytes(&value)?,
+            );
+        }
+
+        Ok(response)
+    }
+}
+
+async fn forward_response<B: MessageBody>(response: HttpResponse<B>) -> Result<ForwardedResponse, String> {
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.as_str().to_string(), value.as_bytes().to_vec()))
+        .collect();
+
+    let body = body::to_bytes(response.into_body()).await.map_err(|e| {
+        let error: Box<dyn std::error::Error> = e.into();
+        error.to_string()
+    })?;
+
+    Ok(ForwardedResponse { status, headers, body })
+}
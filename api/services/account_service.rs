@@ -0,0 +1,22 @@
+use crate::errors::BankError;
+use crate::models::AccountBalance;
+use crate::repository::Store;
+use tracing::instrument;
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn get_balance(store: &dyn Store, user_id: i32) -> Result<AccountBalance, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.find_account_by_user(user_id)
+        .await?
+        .ok_or(BankError::AccountNotFound)
+}
+
+#[instrument(skip_all, fields(account_id = account_id))]
+pub async fn get_account(store: &dyn Store, account_id: i32) -> Result<AccountBalance, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.find_customer_account(account_id)
+        .await?
+        .ok_or(BankError::AccountNotFound)
+}
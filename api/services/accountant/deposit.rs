@@ -1,124 +1,125 @@
 // src/services/accountant/deposit.rs
 
-use sqlx::{MySql, Pool};
-use actix_web::HttpResponse;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
-use crate::models::AccountantData;
-
+use serde_json::json;
+use tracing::{debug, instrument};
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+use crate::models::{AccountantData, AuditAction, AuditEntry, EntryKind, Posting};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::amount::validate_amount;
+use crate::services::idempotency::{self, IdempotencyKey, StoredResponse};
+use crate::services::ledger;
+use crate::services::retry::with_deadlock_retry;
+
+#[instrument(skip_all, fields(accountant_id = accountant_id))]
 pub async fn process_deposit(
-    db_pool: &Pool<MySql>,
+    store: &dyn Store,
+    limits: &LimitsConfig,
     accountant_id: i32,
-    data: &AccountantData
-) -> Result<(), HttpResponse> {
-    println!("DEBUG: Iniciando servicio de depósito.");
+    data: &AccountantData,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    let deposit_amount = validate_amount(data.amount, limits)?;
 
-    let mut transaction = db_pool.begin().await.map_err(|e| {
-        println!("ERROR: Fallo al iniciar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    let response = with_deadlock_retry(|| {
+        deposit_once(store, accountant_id, &data.username, deposit_amount, idempotency_key)
+    })
+    .await?;
 
-    println!("DEBUG: Transacción de base de datos iniciada.");
+    Ok(response)
+}
 
-    // 1. Encontrar el ID de usuario del receptor
-    let recipient_user = sqlx::query!(
-        "SELECT id FROM users WHERE username = ?",
-        data.username
-    )
-    .fetch_one(&mut *transaction)
-    .await
-    .map_err(|e| {
-        if let sqlx::Error::RowNotFound = e {
-            println!("ERROR: El usuario no existe.");
-            HttpResponse::BadRequest().json("El usuario no existe.")
-        } else {
-            println!("ERROR: Fallo al buscar el usuario: {:?}", e);
-            HttpResponse::InternalServerError().finish()
-        }
-    })?;
-    
-    println!("DEBUG: Usuario receptor encontrado con ID: {}", recipient_user.id);
-
-    let deposit_amount = Decimal::from_f64(data.amount)
-        .ok_or_else(|| HttpResponse::BadRequest().json("Monto inválido"))?;
-
-    println!("DEBUG: Monto de depósito convertido a Decimal.");
-
-    // 2. Actualizar el saldo del usuario
-    sqlx::query!(
-        "UPDATE accounts SET balance = balance + ? WHERE user_id = ?",
-        deposit_amount,
-        recipient_user.id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| { 
-        println!("ERROR: Fallo al actualizar el saldo de la cuenta: {:?}", e);
-        HttpResponse::InternalServerError().finish() 
-    })?;
+async fn deposit_once(
+    store: &dyn Store,
+    accountant_id: i32,
+    username: &str,
+    deposit_amount: Decimal,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    let mut uow = store.begin().await?;
+
+    // 0. Si la Idempotency-Key ya se usó, devolver la respuesta guardada
+    if let Some(key) = idempotency_key
+        && let Some(stored) = idempotency::find_stored_response(uow.as_mut(), accountant_id, key).await?
+    {
+        return Ok(stored);
+    }
+
+    // 1-7. Mover el dinero; un rechazo también se guarda para la Idempotency-Key
+    let response = match deposit_money(uow.as_mut(), accountant_id, username, deposit_amount).await {
+        Ok(response) => response,
+        Err(e) => return idempotency::reject(uow, accountant_id, idempotency_key, e).await,
+    };
+
+    // 8. Guardar la respuesta para la Idempotency-Key
+    if let Some(key) = idempotency_key {
+        idempotency::store_response(uow.as_mut(), accountant_id, key, &response).await?;
+    }
+
+    // 9. Confirmar la transacción
+    uow.commit().await?;
+
+    Ok(response)
+}
 
-    println!("DEBUG: Saldo de la cuenta actualizado con éxito.");
+// Los rechazos salen antes de la primera escritura, así no queda nada a medias en la unidad de trabajo
+async fn deposit_money(
+    uow: &mut dyn UnitOfWork,
+    accountant_id: i32,
+    username: &str,
+    deposit_amount: Decimal
+) -> Result<StoredResponse, BankError> {
+    // 1. Encontrar el ID de usuario del receptor
+    let recipient_user = uow
+        .find_user_by_username(username)
+        .await?
+        .ok_or(BankError::UnknownUser)?;
 
-    // 3. Registrar la transacción en la tabla 'transactions'
-    sqlx::query!(
-        "INSERT INTO transactions (sender_id, recipient_id, amount) VALUES (?, ?, ?)",
-        accountant_id,
-        recipient_user.id,
-        deposit_amount
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| {
-        println!("ERROR: Fallo al registrar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Transacción registrada con éxito.");
-
-    // 4. Actualizar el total de dinero en circulación
-    sqlx::query!(
-        "UPDATE total_supply SET total_amount = total_amount + ?",
-        deposit_amount
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| { 
-        println!("ERROR: Fallo al actualizar el total de dinero en circulación: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Total de dinero en circulación actualizado.");
-
-    // 5. Registrar el movimiento en el log de auditoría
-    sqlx::query!(
-        "INSERT INTO audit_log (amount, type, accountant_user_id) VALUES (?, 'deposit', ?)",
-        deposit_amount,
-        accountant_id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| {
-        println!("ERROR: Fallo al registrar el log de auditoría: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    debug!(recipient_user_id = recipient_user.id, "receptor encontrado");
 
-    println!("DEBUG: Movimiento de auditoría registrado.");
+    // 2. Bloquear la cuenta del usuario hasta confirmar
+    let recipient_account = uow
+        .lock_accounts_by_users(&[recipient_user.id])
+        .await?
+        .pop()
+        .ok_or(BankError::AccountNotFound)?;
 
-    // 6. Incrementar el contador de transacciones
-    sqlx::query!(
-        "UPDATE transaction_count SET count = count + 1"
+    // 3. Registrar la transacción en la tabla 'transactions'
+    let transaction_id = uow
+        .insert_transaction(accountant_id, recipient_user.id, deposit_amount)
+        .await?;
+
+    // 4. Asiento contable: el dinero sale de la reserva y entra en la cuenta del usuario
+    let reserve_account_id = uow.reserve_account_id().await?;
+    ledger::post_entry(
+        uow,
+        EntryKind::Deposit,
+        transaction_id,
+        &[
+            Posting { account_id: recipient_account.id, amount: deposit_amount },
+            Posting { account_id: reserve_account_id, amount: -deposit_amount },
+        ],
     )
-    .execute(&mut *transaction)
-    .await.map_err(|e| { 
-        println!("ERROR: Fallo al incrementar el contador de transacciones: {:?}", e);
-        HttpResponse::InternalServerError().finish() 
-    })?;
-    
-    println!("DEBUG: Contador de transacciones incrementado.");
-
-    // 7. Confirmar la transacción
-    transaction.commit().await.map_err(|e| {
-        println!("ERROR: Fallo al confirmar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Transacción completada con éxito.");
-
-    Ok(())
+    .await?;
+
+    // 5. Actualizar el total de dinero en circulación
+    uow.adjust_total_supply(deposit_amount).await?;
+
+    // 6. Registrar el movimiento en el log de auditoría
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::Deposit,
+        actor_user_id: accountant_id,
+        target_user_id: Some(recipient_user.id),
+        amount: Some(deposit_amount),
+        details: None,
+    })
+    .await?;
+
+    // 7. Incrementar el contador de transacciones
+    uow.increment_transaction_count().await?;
+
+    Ok(StoredResponse::ok(json!({
+        "message": "Depósito realizado con éxito"
+    })))
 }
@@ -1,100 +1,126 @@
 // src/services/accountant/withdraw.rs
 
-use sqlx::{MySql, Pool};
-use actix_web::HttpResponse;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
-use crate::models::AccountantData;
+use serde_json::json;
+use tracing::instrument;
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+use crate::models::{AccountantData, AuditAction, AuditEntry, EntryKind, Posting};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::amount::validate_amount;
+use crate::services::idempotency::{self, IdempotencyKey, StoredResponse};
+use crate::services::ledger;
+use crate::services::retry::with_deadlock_retry;
 
+#[instrument(skip_all, fields(accountant_id = accountant_id))]
 pub async fn process_withdrawal(
-    db_pool: &Pool<MySql>,
+    store: &dyn Store,
+    limits: &LimitsConfig,
     accountant_id: i32,
-    data: &AccountantData
-) -> Result<(), HttpResponse> {
-    let mut transaction = db_pool.begin().await.map_err(|_| {
-        HttpResponse::InternalServerError().finish()
-    })?;
+    data: &AccountantData,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    let withdrawal_amount = validate_amount(data.amount, limits)?;
 
-    // 1. Encontrar el ID de usuario del remitente
-    let sender_user = sqlx::query!(
-        "SELECT id FROM users WHERE username = ?",
-        data.username
-    )
-    .fetch_one(&mut *transaction)
+    with_deadlock_retry(|| {
+        withdraw_once(store, accountant_id, &data.username, withdrawal_amount, idempotency_key)
+    })
     .await
-    .map_err(|e| {
-        if let sqlx::Error::RowNotFound = e {
-            HttpResponse::BadRequest().json("El usuario no existe.")
-        } else {
-            HttpResponse::InternalServerError().finish()
-        }
-    })?;
-
-    let withdrawal_amount = Decimal::from_f64(data.amount)
-        .ok_or_else(|| HttpResponse::BadRequest().json("Monto inválido"))?;
-
-    // 2. Obtener el saldo de la cuenta
-    let sender_account = sqlx::query!(
-        "SELECT balance FROM accounts WHERE user_id = ?",
-        sender_user.id
-    )
-    .fetch_one(&mut *transaction)
-    .await.map_err(|_| {
-        HttpResponse::BadRequest().json("No se encontró la cuenta del usuario.")
-    })?;
+}
+
+async fn withdraw_once(
+    store: &dyn Store,
+    accountant_id: i32,
+    username: &str,
+    withdrawal_amount: Decimal,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    let mut uow = store.begin().await?;
+
+    // 0. Si la Idempotency-Key ya se usó, devolver la respuesta guardada
+    if let Some(key) = idempotency_key
+        && let Some(stored) = idempotency::find_stored_response(uow.as_mut(), accountant_id, key).await?
+    {
+        return Ok(stored);
+    }
+
+    // 1-8. Mover el dinero; un rechazo también se guarda para la Idempotency-Key
+    let response = match withdraw_money(uow.as_mut(), accountant_id, username, withdrawal_amount).await {
+        Ok(response) => response,
+        Err(e) => return idempotency::reject(uow, accountant_id, idempotency_key, e).await,
+    };
+
+    // 9. Guardar la respuesta para la Idempotency-Key
+    if let Some(key) = idempotency_key {
+        idempotency::store_response(uow.as_mut(), accountant_id, key, &response).await?;
+    }
+
+    // 10. Confirmar la transacción
+    uow.commit().await?;
+
+    Ok(response)
+}
+
+// Los rechazos salen antes de la primera escritura, así no queda nada a medias en la unidad de trabajo
+async fn withdraw_money(
+    uow: &mut dyn UnitOfWork,
+    accountant_id: i32,
+    username: &str,
+    withdrawal_amount: Decimal
+) -> Result<StoredResponse, BankError> {
+    // 1. Encontrar el ID de usuario del remitente
+    let sender_user = uow
+        .find_user_by_username(username)
+        .await?
+        .ok_or(BankError::UnknownUser)?;
+
+    // 2. Obtener y bloquear el saldo de la cuenta hasta confirmar
+    let sender_account = uow
+        .lock_accounts_by_users(&[sender_user.id])
+        .await?
+        .pop()
+        .ok_or(BankError::AccountNotFound)?;
 
     // 3. Validar que la cuenta tenga fondos suficientes
     if sender_account.balance < withdrawal_amount {
-        return Err(HttpResponse::BadRequest().json("Fondos insuficientes en la cuenta del usuario."));
+        return Err(BankError::InsufficientFunds);
     }
 
-    // 4. Actualizar el saldo del usuario
-    sqlx::query!(
-        "UPDATE accounts SET balance = balance - ? WHERE user_id = ?",
-        withdrawal_amount,
-        sender_user.id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|_| { HttpResponse::InternalServerError().finish() })?;
-
-    // 5. Registrar la transacción en la tabla 'transactions'
-    sqlx::query!(
-        "INSERT INTO transactions (sender_id, recipient_id, amount) VALUES (?, ?, ?)", // recipient_id es NULL para un retiro
-        sender_user.id,
-        accountant_id,
-        withdrawal_amount
+    // 4. Registrar la transacción en la tabla 'transactions' (el contador figura como receptor del retiro)
+    let transaction_id = uow
+        .insert_transaction(sender_user.id, accountant_id, withdrawal_amount)
+        .await?;
+
+    // 5. Asiento contable: el dinero sale de la cuenta del usuario y vuelve a la reserva
+    let reserve_account_id = uow.reserve_account_id().await?;
+    ledger::post_entry(
+        uow,
+        EntryKind::Withdrawal,
+        transaction_id,
+        &[
+            Posting { account_id: sender_account.id, amount: -withdrawal_amount },
+            Posting { account_id: reserve_account_id, amount: withdrawal_amount },
+        ],
     )
-    .execute(&mut *transaction)
-    .await.map_err(|_| { HttpResponse::InternalServerError().finish() })?;
+    .await?;
 
     // 6. Actualizar el total de dinero en circulación
-    sqlx::query!(
-        "UPDATE total_supply SET total_amount = total_amount - ?",
-        withdrawal_amount
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|_| { HttpResponse::InternalServerError().finish() })?;
+    uow.adjust_total_supply(-withdrawal_amount).await?;
 
     // 7. Registrar el movimiento en el log de auditoría
-    sqlx::query!(
-        "INSERT INTO audit_log (amount, type, accountant_user_id) VALUES (?, 'withdrawal', ?)",
-        withdrawal_amount,
-        accountant_id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|_| { HttpResponse::InternalServerError().finish() })?;
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::Withdrawal,
+        actor_user_id: accountant_id,
+        target_user_id: Some(sender_user.id),
+        amount: Some(withdrawal_amount),
+        details: None,
+    })
+    .await?;
 
     // 8. Incrementar el contador de transacciones
-    sqlx::query!(
-        "UPDATE transaction_count SET count = count + 1"
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|_| { HttpResponse::InternalServerError().finish() })?;
-
-    // 9. Confirmar la transacción
-    transaction.commit().await.map_err(|_| {
-        HttpResponse::InternalServerError().finish()
-    })?;
+    uow.increment_transaction_count().await?;
 
-    Ok(())
+    Ok(StoredResponse::ok(json!({
+        "message": "Retiro realizado con éxito"
+    })))
 }
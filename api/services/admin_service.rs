@@ -0,0 +1,162 @@
+use rand::distributions::{Alphanumeric, DistString};
+use serde_json::json;
+use tracing::instrument;
+
+use crate::errors::BankError;
+use crate::models::{AuditAction, AuditEntry, User, UserData, UserSummary};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::registration_service;
+
+// Roles que se pueden asignar; deben existir en `role_permissions`
+pub const ROLES: &[&str] = &["user", "accountant", "admin"];
+
+const TEMPORARY_PASSWORD_LENGTH: usize = 16;
+
+async fn find_target(uow: &mut dyn UnitOfWork, user_id: i32) -> Result<User, BankError> {
+    uow.find_user_by_id(user_id).await?.ok_or(BankError::UnknownUser)
+}
+
+// Un administrador no puede quitarse el rol ni deshabilitarse a sí mismo
+fn ensure_not_self(admin_id: i32, user_id: i32) -> Result<(), BankError> {
+    if admin_id == user_id {
+        return Err(BankError::InvalidRequest(
+            "Un administrador no puede modificar su propia cuenta.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub async fn list_users(store: &dyn Store) -> Result<Vec<UserSummary>, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.list_users().await
+}
+
+// Crea el usuario con su cuenta y lo registra en la auditoría. Si no hay `admin_id`, el
+// usuario se crea a sí mismo (el primer administrador, desde la línea de comandos).
+#[instrument(skip_all, fields(username = %user_data.username, role = %role))]
+pub async fn create_user(
+    store: &dyn Store,
+    admin_id: Option<i32>,
+    user_data: &UserData,
+    role: &str,
+) -> Result<i32, BankError> {
+    if !ROLES.contains(&role) {
+        return Err(BankError::InvalidRequest(format!("El rol '{}' no existe.", role)));
+    }
+
+    registration_service::validate_username(&user_data.username)?;
+    registration_service::validate_password(&user_data.username, &user_data.password)?;
+
+    let hashed_password = bcrypt::hash(&user_data.password, 10)
+        .map_err(|e| BankError::Internal(format!("no se pudo generar el hash: {}", e)))?;
+
+    let mut uow = store.begin().await?;
+
+    let user_id = uow.insert_user(&user_data.username, &hashed_password, role).await?;
+    uow.insert_account(user_id).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::UserCreated,
+        actor_user_id: admin_id.unwrap_or(user_id),
+        target_user_id: Some(user_id),
+        amount: None,
+        details: Some(json!({ "role": role })),
+    })
+    .await?;
+
+    // Si algo falla antes de este punto, la unidad de trabajo se revierte al soltarla
+    uow.commit().await?;
+
+    Ok(user_id)
+}
+
+#[instrument(skip_all, fields(admin_id = admin_id, user_id = user_id))]
+pub async fn change_role(store: &dyn Store, admin_id: i32, user_id: i32, role: &str) -> Result<(), BankError> {
+    ensure_not_self(admin_id, user_id)?;
+
+    if !ROLES.contains(&role) {
+        return Err(BankError::InvalidRequest(format!("El rol '{}' no existe.", role)));
+    }
+
+    let mut uow = store.begin().await?;
+
+    let user = find_target(uow.as_mut(), user_id).await?;
+    uow.update_user_role(user_id, role).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::RoleChanged,
+        actor_user_id: admin_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: Some(json!({ "from": user.role, "to": role })),
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
+
+// Deshabilitar también revoca los refresh tokens; los access tokens dejan de valer
+// porque el middleware comprueba el estado del usuario en cada petición
+#[instrument(skip_all, fields(admin_id = admin_id, user_id = user_id))]
+pub async fn set_disabled(store: &dyn Store, admin_id: i32, user_id: i32, disabled: bool) -> Result<(), BankError> {
+    ensure_not_self(admin_id, user_id)?;
+
+    let mut uow = store.begin().await?;
+
+    find_target(uow.as_mut(), user_id).await?;
+    uow.set_user_disabled(user_id, disabled).await?;
+
+    if disabled {
+        uow.revoke_user_refresh_tokens(user_id).await?;
+        uow.terminate_user_sessions(user_id).await?;
+    }
+
+    uow.insert_audit_log(&AuditEntry {
+        action: if disabled { AuditAction::UserDisabled } else { AuditAction::UserEnabled },
+        actor_user_id: admin_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: None,
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
+
+// Genera una contraseña temporal, cierra las sesiones abiertas, quita la verificación en dos
+// pasos (quien pide el reseteo suele haber perdido también el autenticador) y devuelve la
+// contraseña una sola vez
+#[instrument(skip_all, fields(admin_id = admin_id, user_id = user_id))]
+pub async fn reset_credentials(store: &dyn Store, admin_id: i32, user_id: i32) -> Result<String, BankError> {
+    let temporary_password = Alphanumeric.sample_string(&mut rand::thread_rng(), TEMPORARY_PASSWORD_LENGTH);
+    let hashed_password = bcrypt::hash(&temporary_password, 10)
+        .map_err(|e| BankError::Internal(format!("no se pudo generar el hash: {}", e)))?;
+
+    let mut uow = store.begin().await?;
+
+    find_target(uow.as_mut(), user_id).await?;
+    uow.update_password_hash(user_id, &hashed_password).await?;
+    uow.increment_token_version(user_id).await?;
+    uow.revoke_user_refresh_tokens(user_id).await?;
+    uow.terminate_user_sessions(user_id).await?;
+    uow.delete_totp(user_id).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::CredentialsReset,
+        actor_user_id: admin_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: None,
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(temporary_password)
+}
@@ -0,0 +1,32 @@
+use rust_decimal::Decimal;
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+
+// Los límites de los montos vienen de la sección `[limits]` de la configuración
+pub fn validate_amount(amount: Decimal, limits: &LimitsConfig) -> Result<Decimal, BankError> {
+    if amount.is_sign_negative() && !amount.is_zero() {
+        return Err(BankError::InvalidAmount("La cantidad no puede ser negativa.".to_string()));
+    }
+
+    if amount.is_zero() {
+        return Err(BankError::InvalidAmount("La cantidad debe ser mayor a 0.".to_string()));
+    }
+
+    // "10.50" es válido, "10.505" no
+    let amount = amount.normalize();
+    if amount.scale() > limits.max_amount_scale {
+        return Err(BankError::InvalidAmount(format!(
+            "La cantidad admite como máximo {} decimales.",
+            limits.max_amount_scale
+        )));
+    }
+
+    if amount > limits.max_amount {
+        return Err(BankError::InvalidAmount(format!(
+            "La cantidad no puede ser mayor a {}.",
+            limits.max_amount
+        )));
+    }
+
+    Ok(amount)
+}
@@ -0,0 +1,70 @@
+use chrono::{Duration, Utc};
+use tracing::instrument;
+
+use crate::errors::BankError;
+use crate::middleware::jwt_auth::Claims;
+use crate::middleware::rbac::AuthenticatedUser;
+use crate::models::Permission;
+use crate::repository::Store;
+
+// `last_seen_at` se actualiza como mucho una vez por minuto, no en cada petición
+const SESSION_TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+// Comprueba que el token no esté revocado ni sea anterior al último cambio de contraseña, que
+// su sesión siga abierta y que el usuario siga activo, y carga los permisos de su rol actual
+// (un cambio de rol se aplica sin esperar a que expire el token)
+#[instrument(skip_all, fields(user_id = claims.sub))]
+pub async fn authenticate(store: &dyn Store, claims: Claims) -> Result<AuthenticatedUser, BankError> {
+    let mut uow = store.begin().await?;
+
+    if uow.is_access_token_revoked(&claims.jti).await? {
+        return Err(BankError::Unauthorized);
+    }
+
+    // Antes que la sesión: deshabilitar cierra las sesiones, y el cliente debe saber por qué
+    let user = uow
+        .find_user_by_id(claims.sub)
+        .await?
+        .ok_or(BankError::Unauthorized)?;
+
+    if user.disabled {
+        return Err(BankError::UserDisabled);
+    }
+
+    let session = uow
+        .find_session(&claims.sid)
+        .await?
+        .filter(|session| session.user_id == claims.sub && session.terminated_at.is_none())
+        .ok_or(BankError::Unauthorized)?;
+
+    // La contraseña cambió después de emitir el token
+    if user.token_version != claims.ver {
+        return Err(BankError::Unauthorized);
+    }
+
+    // Los nombres desconocidos en la base de datos se ignoran
+    let permissions = uow
+        .find_role_permissions(&user.role)
+        .await?
+        .iter()
+        .filter_map(|name| Permission::from_name(name))
+        .collect();
+
+    let two_factor_pending =
+        uow.role_requires_two_factor(&user.role).await? && !uow.is_totp_enabled(user.id).await?;
+
+    let now = Utc::now();
+    if now - session.last_seen_at >= Duration::seconds(SESSION_TOUCH_INTERVAL_SECONDS) {
+        uow.touch_session(&session.id, now).await?;
+        uow.commit().await?;
+    }
+
+    Ok(AuthenticatedUser {
+        user_id: user.id,
+        role: user.role,
+        jti: claims.jti,
+        session_id: session.id,
+        permissions,
+        two_factor_pending,
+    })
+}
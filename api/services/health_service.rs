@@ -0,0 +1,184 @@
+// src/services/health_service.rs
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::errors::BankError;
+use crate::repository::{Store, UnitOfWork};
+use crate::MIGRATOR;
+
+// Las sondas del orquestador tienen su propio timeout; cada comprobación se corta antes
+// para responder con el detalle en vez de dejar la petición colgada
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CheckResult {
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HealthReport {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<&'static str, CheckResult>,
+}
+
+impl HealthReport {
+    fn from_checks(checks: BTreeMap<&'static str, CheckResult>) -> HealthReport {
+        let status = if checks.values().all(|check| check.status == CheckStatus::Ok) {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Failed
+        };
+
+        HealthReport { status, checks }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == CheckStatus::Ok
+    }
+}
+
+// El proceso responde; no toca la base de datos para que una caída de MySQL no
+// provoque reinicios en cadena
+pub fn liveness() -> HealthReport {
+    let started = Instant::now();
+    let mut checks = BTreeMap::new();
+    checks.insert("process", check_result(Ok(()), started));
+
+    HealthReport::from_checks(checks)
+}
+
+// La instancia puede recibir tráfico: hay conexión, el esquema está al día y existen las
+// filas únicas que los servicios dan por supuestas
+pub async fn readiness(store: &dyn Store) -> HealthReport {
+    let mut checks = BTreeMap::new();
+
+    // 1. Conexión: abrir una unidad de trabajo y hacer una consulta mínima
+    let started = Instant::now();
+    let connected = with_timeout(async {
+        let mut uow = store.begin().await.map_err(database_error)?;
+        uow.ping().await.map_err(database_error)?;
+        Ok::<_, String>(uow)
+    })
+    .await;
+
+    let mut uow = match connected {
+        Ok(uow) => {
+            checks.insert("database", check_result(Ok(()), started));
+            uow
+        }
+        Err(error) => {
+            checks.insert("database", check_result(Err(error), started));
+            for name in ["migrations", "singletons"] {
+                checks.insert(name, skipped());
+            }
+            return HealthReport::from_checks(checks);
+        }
+    };
+
+    // 2. Migraciones: las que trae este binario ya están aplicadas. Una versión posterior
+    // se acepta, porque durante un despliegue la instancia nueva migra antes que la vieja
+    let started = Instant::now();
+    let migrations = with_timeout(check_migrations(uow.as_mut())).await;
+    checks.insert("migrations", check_result(migrations, started));
+
+    // 3. Filas únicas de `total_supply` y `transaction_count`
+    let started = Instant::now();
+    let singletons = with_timeout(check_singletons(uow.as_mut())).await;
+    checks.insert("singletons", check_result(singletons, started));
+
+    // Solo lectura: la unidad de trabajo se descarta sin confirmar
+    HealthReport::from_checks(checks)
+}
+
+async fn check_migrations(uow: &mut dyn UnitOfWork) -> Result<(), String> {
+    let expected = MIGRATOR.iter().map(|migration| migration.version).max();
+    let applied = uow.latest_migration_version().await.map_err(database_error)?;
+
+    match (applied, expected) {
+        (_, None) => Ok(()),
+        (Some(applied), Some(expected)) if applied >= expected => Ok(()),
+        (applied, Some(expected)) => Err(format!(
+            "Esquema en la versión {}, se esperaba la {}.",
+            applied.map_or("ninguna".to_string(), |version| version.to_string()),
+            expected
+        )),
+    }
+}
+
+async fn check_singletons(uow: &mut dyn UnitOfWork) -> Result<(), String> {
+    let counts = uow.singleton_row_counts().await.map_err(database_error)?;
+
+    let mut problems = Vec::new();
+    for (table, rows) in [
+        ("total_supply", counts.total_supply),
+        ("transaction_count", counts.transaction_count),
+    ] {
+        if rows != 1 {
+            problems.push(format!("{} tiene {} filas", table, rows));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Se esperaba una fila: {}.", problems.join(", ")))
+    }
+}
+
+async fn with_timeout<T, F>(check: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Sin respuesta en {} segundos.", CHECK_TIMEOUT.as_secs())),
+    }
+}
+
+// El endpoint es público: el detalle del error se queda en el log
+fn database_error(error: BankError) -> String {
+    warn!(error = %error, "comprobación de salud fallida");
+    "La base de datos no responde.".to_string()
+}
+
+fn check_result(result: Result<(), String>, started: Instant) -> CheckResult {
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    match result {
+        Ok(()) => CheckResult {
+            status: CheckStatus::Ok,
+            latency_ms,
+            error: None,
+        },
+        Err(error) => CheckResult {
+            status: CheckStatus::Failed,
+            latency_ms,
+            error: Some(error),
+        },
+    }
+}
+
+// Comprobaciones que no se llegan a hacer porque la conexión ha fallado
+fn skipped() -> CheckResult {
+    CheckResult {
+        status: CheckStatus::Failed,
+        latency_ms: 0.0,
+        error: Some("Sin conexión a la base de datos.".to_string()),
+    }
+}
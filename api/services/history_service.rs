@@ -0,0 +1,41 @@
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+use crate::models::{HistoryPage, HistoryQuery};
+use crate::repository::Store;
+use tracing::instrument;
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn list_transactions(
+    store: &dyn Store,
+    limits: &LimitsConfig,
+    user_id: i32,
+    query: &HistoryQuery
+) -> Result<HistoryPage, BankError> {
+    if let (Some(min), Some(max)) = (query.min_amount, query.max_amount)
+        && min > max
+    {
+        return Err(BankError::InvalidRequest("El monto mínimo no puede ser mayor al máximo.".to_string()));
+    }
+
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from >= to
+    {
+        return Err(BankError::InvalidRequest("El rango de fechas no es válido.".to_string()));
+    }
+
+    let limit = query.limit.unwrap_or(limits.default_page_size).clamp(1, limits.max_page_size);
+
+    let mut uow = store.begin().await?;
+
+    // Se pide un registro extra para saber si existe una página siguiente
+    let mut entries = uow.list_transactions(user_id, query, limit + 1).await?;
+
+    let next_cursor = if entries.len() > limit as usize {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| entry.id)
+    } else {
+        None
+    };
+
+    Ok(HistoryPage { items: entries, next_cursor })
+}
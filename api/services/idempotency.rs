@@ -0,0 +1,132 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use crate::errors::BankError;
+use crate::models::IdempotencyRecord;
+use crate::repository::UnitOfWork;
+
+const MAX_KEY_LENGTH: usize = 255;
+
+// Clave enviada por el cliente junto con la huella de la petición que la acompañó
+pub struct IdempotencyKey {
+    pub key: String,
+    pub fingerprint: String,
+}
+
+impl IdempotencyKey {
+    // La huella cubre el endpoint y el cuerpo, así la misma clave no sirve para otra operación
+    pub fn new<T: Serialize>(key: &str, endpoint: &str, body: &T) -> Result<IdempotencyKey, BankError> {
+        let key = key.trim();
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return Err(BankError::InvalidRequest(format!(
+                "La Idempotency-Key debe tener entre 1 y {} caracteres.",
+                MAX_KEY_LENGTH
+            )));
+        }
+
+        let serialized_body = serde_json::to_vec(body)
+            .map_err(|e| BankError::Internal(format!("no se pudo serializar la petición: {}", e)))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(endpoint.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(&serialized_body);
+
+        Ok(IdempotencyKey {
+            key: key.to_string(),
+            fingerprint: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+// Resultado final de una operación, tal como se devuelve (y se repite) al cliente
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: Value,
+    pub replayed: bool,
+}
+
+impl StoredResponse {
+    pub fn ok(body: Value) -> StoredResponse {
+        StoredResponse { status: 200, body, replayed: false }
+    }
+
+    // Rechazos de negocio definitivos: repetir la misma petición debe dar el mismo error, no
+    // volver a intentarla. Los errores internos no se guardan y el cliente puede reintentar.
+    pub fn rejected(error: &BankError) -> Option<StoredResponse> {
+        match error {
+            BankError::InsufficientFunds
+            | BankError::UnknownRecipient
+            | BankError::UnknownUser
+            | BankError::AccountNotFound
+            | BankError::SelfTransfer => {
+                let problem = error.problem_details();
+                Some(StoredResponse {
+                    status: problem.status(),
+                    body: serde_json::to_value(&problem).unwrap_or_default(),
+                    replayed: false,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Busca la clave dentro de la unidad de trabajo abierta por el servicio. En MySQL, si dos
+// peticiones con la misma clave llegan a la vez, la segunda queda bloqueada (o entra en
+// deadlock y se reintenta) hasta que la primera confirma, y entonces recibe la respuesta guardada.
+pub async fn find_stored_response(
+    uow: &mut dyn UnitOfWork,
+    user_id: i32,
+    key: &IdempotencyKey
+) -> Result<Option<StoredResponse>, BankError> {
+    let stored = match uow.find_idempotency_record(user_id, &key.key).await? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    if stored.request_hash != key.fingerprint {
+        return Err(BankError::IdempotencyKeyReused);
+    }
+
+    Ok(Some(StoredResponse {
+        status: stored.response_status,
+        body: stored.response_body,
+        replayed: true,
+    }))
+}
+
+// Guarda la respuesta en la misma unidad de trabajo que el movimiento de dinero
+pub async fn store_response(
+    uow: &mut dyn UnitOfWork,
+    user_id: i32,
+    key: &IdempotencyKey,
+    response: &StoredResponse
+) -> Result<(), BankError> {
+    let record = IdempotencyRecord {
+        request_hash: key.fingerprint.clone(),
+        response_status: response.status,
+        response_body: response.body.clone(),
+    };
+
+    uow.insert_idempotency_record(user_id, &key.key, &record).await
+}
+
+// Termina la operación con `error`. Si es un rechazo definitivo y la petición trae clave, lo
+// guarda y confirma la unidad de trabajo, que hasta el rechazo no ha escrito nada más; si no,
+// la unidad de trabajo se descarta.
+pub async fn reject(
+    mut uow: Box<dyn UnitOfWork>,
+    user_id: i32,
+    key: Option<&IdempotencyKey>,
+    error: BankError
+) -> Result<StoredResponse, BankError> {
+    if let Some(key) = key
+        && let Some(response) = StoredResponse::rejected(&error)
+    {
+        store_response(uow.as_mut(), user_id, key, &response).await?;
+        uow.commit().await?;
+    }
+
+    Err(error)
+}
@@ -0,0 +1,46 @@
+use rust_decimal::Decimal;
+use tracing::instrument;
+use crate::errors::BankError;
+use crate::models::{EntryKind, LedgerReport, Posting};
+use crate::repository::{Store, UnitOfWork};
+
+// Registra un asiento balanceado y actualiza el saldo cacheado de cada cuenta.
+// Los apuntes se aplican en el orden recibido: las cuentas de clientes primero (por id)
+// y la reserva al final, para mantener el mismo orden de bloqueos en todos los servicios.
+pub async fn post_entry(
+    uow: &mut dyn UnitOfWork,
+    kind: EntryKind,
+    transaction_id: i32,
+    postings: &[Posting]
+) -> Result<i32, BankError> {
+    let total: Decimal = postings.iter().map(|posting| posting.amount).sum();
+    if postings.len() < 2 || !total.is_zero() {
+        return Err(BankError::Internal(format!("asiento desbalanceado ({:?}): {:?}", kind, postings)));
+    }
+
+    uow.insert_journal_entry(kind, transaction_id, postings).await
+}
+
+// Comprueba que cada asiento sume cero, que los saldos coincidan con la suma de sus
+// apuntes y que el dinero en circulación sea exactamente lo que falta en la reserva
+#[instrument(skip_all)]
+pub async fn check_invariants(store: &dyn Store) -> Result<LedgerReport, BankError> {
+    let mut uow = store.begin().await?;
+
+    let unbalanced_entries = uow.unbalanced_entries().await?;
+    let mismatched_accounts = uow.mismatched_accounts().await?;
+    let total_supply = uow.total_supply().await?;
+    let reserve_balance = uow.reserve_balance().await?;
+
+    let consistent = unbalanced_entries.is_empty()
+        && mismatched_accounts.is_empty()
+        && total_supply + reserve_balance == Decimal::ZERO;
+
+    Ok(LedgerReport {
+        consistent,
+        unbalanced_entries,
+        mismatched_accounts,
+        total_supply,
+        reserve_balance,
+    })
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use tracing::instrument;
+
+use crate::config::{LimitsConfig, ThrottlePolicy};
+use crate::errors::BankError;
+use crate::models::{AuthEvent, AuthEventKind, AuthEventRecord, AuthEventQuery, ThrottleScope};
+use crate::repository::{Store, UnitOfWork};
+
+// Los fallos más antiguos que esto ya no cuentan
+const FAILURE_WINDOW_HOURS: i64 = 24;
+const DEFAULT_EVENT_LIMIT: u32 = 50;
+const MAX_EVENT_LIMIT: u32 = 500;
+
+impl ThrottlePolicy {
+    // Cuánto queda bloqueado el sujeto tras su fallo número `failures`
+    fn delay_after(&self, failures: i32) -> Option<Duration> {
+        if failures >= self.lockout_failures {
+            return Some(Duration::minutes(self.lockout_minutes));
+        }
+        if failures <= self.free_failures {
+            return None;
+        }
+
+        let exponent = (failures - self.free_failures - 1).min(30) as u32;
+        let seconds = self.base_delay_seconds.saturating_mul(1 << exponent).min(self.max_delay_seconds);
+        Some(Duration::seconds(seconds))
+    }
+}
+
+fn subjects<'a>(username: &'a str, client_ip: Option<&'a str>) -> Vec<(ThrottleScope, &'a str)> {
+    let mut subjects = vec![(ThrottleScope::Username, username)];
+    if let Some(ip) = client_ip {
+        subjects.push((ThrottleScope::Ip, ip));
+    }
+    subjects
+}
+
+// Devuelve los segundos que quedan de bloqueo, si el usuario o la IP están bloqueados
+pub async fn retry_after(
+    uow: &mut dyn UnitOfWork,
+    username: &str,
+    client_ip: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<i64>, BankError> {
+    let mut retry_after = None;
+
+    for (scope, subject) in subjects(username, client_ip) {
+        if let Some(locked_until) = uow.find_login_lock(scope, subject).await?
+            && locked_until > now
+        {
+            // Se redondea hacia arriba para no invitar a reintentar antes de tiempo
+            let seconds = ((locked_until - now).num_milliseconds() + 999) / 1000;
+            retry_after = retry_after.max(Some(seconds));
+        }
+    }
+
+    Ok(retry_after)
+}
+
+pub async fn record_blocked(
+    uow: &mut dyn UnitOfWork,
+    username: &str,
+    client_ip: Option<&str>,
+    retry_after: i64,
+) -> Result<(), BankError> {
+    uow.insert_auth_event(&AuthEvent {
+        kind: AuthEventKind::LoginBlocked,
+        username: Some(username.to_string()),
+        user_id: None,
+        ip_address: client_ip.map(str::to_string),
+        details: Some(json!({ "retry_after": retry_after })),
+    })
+    .await
+}
+
+// Cuenta el fallo de login para el usuario y la IP, y los bloquea según su política
+pub async fn record_failure(
+    uow: &mut dyn UnitOfWork,
+    limits: &LimitsConfig,
+    username: &str,
+    user_id: Option<i32>,
+    client_ip: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), BankError> {
+    record_attempt(uow, limits, AuthEventKind::LoginFailed, username, user_id, client_ip, now).await
+}
+
+// Como `record_failure`, para otros intentos que cuentan contra el mismo límite (cambios de
+// contraseña fallidos y peticiones de restablecimiento); `kind` es el evento que se registra
+pub async fn record_attempt(
+    uow: &mut dyn UnitOfWork,
+    limits: &LimitsConfig,
+    kind: AuthEventKind,
+    username: &str,
+    user_id: Option<i32>,
+    client_ip: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), BankError> {
+    let window_start = now - Duration::hours(FAILURE_WINDOW_HOURS);
+    let event = |kind: AuthEventKind, details: serde_json::Value| AuthEvent {
+        kind,
+        username: Some(username.to_string()),
+        user_id,
+        ip_address: client_ip.map(str::to_string),
+        details: Some(details),
+    };
+
+    for (scope, subject) in subjects(username, client_ip) {
+        let failures = uow.record_login_failure(scope, subject, now, window_start).await?;
+
+        if scope == ThrottleScope::Username {
+            uow.insert_auth_event(&event(kind, json!({ "failures": failures }))).await?;
+        }
+
+        let policy = match scope {
+            ThrottleScope::Username => &limits.username_throttle,
+            ThrottleScope::Ip => &limits.ip_throttle,
+        };
+        let Some(delay) = policy.delay_after(failures) else {
+            continue;
+        };
+        uow.lock_login_subject(scope, subject, now + delay).await?;
+
+        if failures >= policy.lockout_failures {
+            let kind = match scope {
+                ThrottleScope::Username => AuthEventKind::AccountLocked,
+                ThrottleScope::Ip => AuthEventKind::IpLocked,
+            };
+            let details = json!({ "failures": failures, "locked_seconds": delay.num_seconds() });
+            uow.insert_auth_event(&event(kind, details)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Un login correcto borra los fallos del usuario, no los de la IP
+pub async fn record_success(uow: &mut dyn UnitOfWork, username: &str) -> Result<(), BankError> {
+    uow.clear_login_attempts(ThrottleScope::Username, username).await
+}
+
+#[instrument(skip_all)]
+pub async fn list_events(store: &dyn Store, query: &AuthEventQuery) -> Result<Vec<AuthEventRecord>, BankError> {
+    let limit = query.limit.unwrap_or(DEFAULT_EVENT_LIMIT).clamp(1, MAX_EVENT_LIMIT);
+
+    let mut uow = store.begin().await?;
+    uow.list_auth_events(limit).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_after_the_free_failures_and_ends_in_a_lockout() {
+        let policy = LimitsConfig::default().username_throttle;
+        let delays: Vec<Option<i64>> = (1..=10)
+            .map(|failures| policy.delay_after(failures).map(|delay| delay.num_seconds()))
+            .collect();
+
+        assert_eq!(
+            delays,
+            vec![None, None, None, Some(2), Some(4), Some(8), Some(16), Some(32), Some(64), Some(900)]
+        );
+    }
+
+    #[test]
+    fn delay_is_capped_before_the_lockout() {
+        let policy = LimitsConfig::default().ip_throttle;
+        assert_eq!(policy.delay_after(49), Some(Duration::seconds(policy.max_delay_seconds)));
+        assert_eq!(policy.delay_after(50), Some(Duration::minutes(policy.lockout_minutes)));
+    }
+}
@@ -1,3 +1,20 @@
 pub mod user_service;
 pub mod transaction_service;
 pub mod accountant;
+pub mod amount;
+pub mod retry;
+pub mod idempotency;
+pub mod ledger;
+pub mod account_service;
+pub mod history_service;
+pub mod token_service;
+pub mod authorization;
+pub mod admin_service;
+pub mod registration_service;
+pub mod password_service;
+pub mod totp;
+pub mod two_factor_service;
+pub mod login_throttle;
+pub mod session_service;
+pub mod jwt_keys;
+pub mod health_service;
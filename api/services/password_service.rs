@@ -0,0 +1,165 @@
+use chrono::{Duration, Utc};
+use bcrypt::verify;
+use tracing::{error, instrument};
+
+use crate::config::{AuthConfig, LimitsConfig};
+use crate::errors::BankError;
+use crate::models::{AuditAction, AuditEntry, AuthEventKind};
+use crate::notifier::{Notification, Notifier};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::login_throttle;
+use crate::services::registration_service::validate_password;
+use crate::services::token_service::{generate_opaque_token, hash_token};
+
+// Guarda la nueva contraseña y cierra todas las sesiones: los access tokens dejan de
+// valer por la versión y los refresh tokens quedan revocados
+async fn replace_password(
+    uow: &mut dyn UnitOfWork,
+    user_id: i32,
+    new_password: &str,
+    action: AuditAction,
+) -> Result<(), BankError> {
+    let hashed_password = bcrypt::hash(new_password, 10)
+        .map_err(|e| BankError::Internal(format!("no se pudo generar el hash: {}", e)))?;
+
+    uow.update_password_hash(user_id, &hashed_password).await?;
+    uow.increment_token_version(user_id).await?;
+    uow.revoke_user_refresh_tokens(user_id).await?;
+    uow.terminate_user_sessions(user_id).await?;
+    uow.consume_password_resets(user_id).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action,
+        actor_user_id: user_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: None,
+    })
+    .await
+}
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn change_password(
+    store: &dyn Store,
+    limits: &LimitsConfig,
+    user_id: i32,
+    current_password: &str,
+    new_password: &str,
+    client_ip: Option<&str>,
+) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+    let user = uow.find_user_by_id(user_id).await?.ok_or(BankError::Unauthorized)?;
+
+    // 1. Con un token robado se podría adivinar la contraseña actual: los fallos cuentan
+    // contra el mismo límite que los del login
+    if let Some(retry_after) = login_throttle::retry_after(uow.as_mut(), &user.username, client_ip, now).await? {
+        login_throttle::record_blocked(uow.as_mut(), &user.username, client_ip, retry_after).await?;
+        uow.commit().await?;
+        return Err(BankError::TooManyAttempts(retry_after));
+    }
+
+    // 2. Comprobar la contraseña actual
+    if !verify(current_password, &user.password_hash).unwrap_or(false) {
+        let kind = AuthEventKind::PasswordChangeFailed;
+        login_throttle::record_attempt(uow.as_mut(), limits, kind, &user.username, Some(user.id), client_ip, now)
+            .await?;
+        uow.commit().await?;
+        return Err(BankError::InvalidCredentials);
+    }
+    login_throttle::record_success(uow.as_mut(), &user.username).await?;
+
+    // 3. Validar y guardar la nueva
+    validate_password(&user.username, new_password)?;
+    replace_password(uow.as_mut(), user.id, new_password, AuditAction::PasswordChanged).await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
+
+// Envía un token de un solo uso. Si el usuario no existe no se hace nada, y el cliente
+// recibe la misma respuesta, para no revelar qué nombres están registrados. Cada petición
+// cuenta contra el límite del login, por usuario y por IP, exista o no el usuario.
+#[instrument(skip_all, fields(username = %username))]
+pub async fn request_reset(
+    store: &dyn Store,
+    notifier: &dyn Notifier,
+    config: &AuthConfig,
+    limits: &LimitsConfig,
+    username: &str,
+    client_ip: Option<&str>,
+) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+    let username = username.trim();
+
+    // 1. Rechazar la petición si el usuario o la IP están bloqueados
+    if let Some(retry_after) = login_throttle::retry_after(uow.as_mut(), username, client_ip, now).await? {
+        login_throttle::record_blocked(uow.as_mut(), username, client_ip, retry_after).await?;
+        uow.commit().await?;
+        return Err(BankError::TooManyAttempts(retry_after));
+    }
+
+    // 2. Contar la petición
+    let user = uow.find_user_by_username(username).await?;
+    let kind = AuthEventKind::PasswordResetRequested;
+    let user_id = user.as_ref().map(|user| user.id);
+    login_throttle::record_attempt(uow.as_mut(), limits, kind, username, user_id, client_ip, now).await?;
+
+    let user = match user {
+        Some(user) if !user.disabled => user,
+        _ => {
+            uow.commit().await?;
+            return Ok(());
+        }
+    };
+
+    let token = generate_opaque_token();
+    let expires_at = Utc::now() + Duration::minutes(config.password_reset_ttl_minutes);
+    uow.insert_password_reset(user.id, &hash_token(&token), expires_at).await?;
+
+    // 3. Se confirma antes de notificar para no enviar un token que no existe
+    uow.commit().await?;
+
+    // Un fallo del notificador no se devuelve: un 500 solo para usuarios que existen los
+    // delataría. El usuario puede volver a pedir el token.
+    let notification = Notification::PasswordReset { username: user.username, token, expires_at };
+    if let Err(e) = notifier.send(&notification).await {
+        error!(error = %e, "no se pudo enviar el token de restablecimiento");
+    }
+
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub async fn reset_password(store: &dyn Store, token: &str, new_password: &str) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+
+    // 1. Buscar el token (bloqueado) y comprobar que siga vigente
+    let password_reset = uow
+        .find_password_reset(&hash_token(token.trim()))
+        .await?
+        .ok_or(BankError::InvalidResetToken)?;
+
+    if password_reset.used_at.is_some() || password_reset.expires_at <= Utc::now() {
+        return Err(BankError::InvalidResetToken);
+    }
+
+    let user = uow
+        .find_user_by_id(password_reset.user_id)
+        .await?
+        .ok_or(BankError::InvalidResetToken)?;
+
+    if user.disabled {
+        return Err(BankError::UserDisabled);
+    }
+
+    // 2. Validar y guardar la nueva contraseña; el token queda consumido
+    validate_password(&user.username, new_password)?;
+    replace_password(uow.as_mut(), user.id, new_password, AuditAction::PasswordReset).await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
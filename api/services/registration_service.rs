@@ -0,0 +1,207 @@
+use chrono::{Duration, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use serde_json::json;
+use tracing::instrument;
+
+use crate::config::RegistrationConfig;
+use crate::errors::BankError;
+use crate::models::{AuditAction, AuditEntry, InviteCode, RegisterRequest};
+use crate::repository::Store;
+
+const USERNAME_MIN_LENGTH: usize = 3;
+const USERNAME_MAX_LENGTH: usize = 32;
+const PASSWORD_MIN_LENGTH: usize = 10;
+// bcrypt ignora todo lo que pase de 72 bytes
+const PASSWORD_MAX_BYTES: usize = 72;
+
+const INVITE_CODE_LENGTH: usize = 12;
+
+// Contraseñas que cumplen la longitud mínima pero aparecen en todas las filtraciones
+const COMMON_PASSWORDS: &[&str] = &[
+    "0123456789",
+    "1234567890",
+    "12345678910",
+    "1111111111",
+    "0987654321",
+    "1q2w3e4r5t",
+    "qwertyuiop",
+    "qwerty1234",
+    "qwerty12345",
+    "asdfghjkl1",
+    "abcdefghij",
+    "password12",
+    "password123",
+    "password1234",
+    "passw0rd123",
+    "contraseña",
+    "contraseña1",
+    "contraseña123",
+    "iloveyou12",
+    "iloveyou123",
+    "welcome123",
+    "letmein123",
+    "football123",
+    "superman123",
+    "dragon1234",
+    "administrator",
+    "friendbank",
+    "friendbank123",
+];
+
+// Letras, dígitos, '.', '_' y '-'; debe empezar por letra
+pub fn validate_username(username: &str) -> Result<(), BankError> {
+    let length = username.chars().count();
+    if !(USERNAME_MIN_LENGTH..=USERNAME_MAX_LENGTH).contains(&length) {
+        return Err(BankError::InvalidRequest(format!(
+            "El nombre de usuario debe tener entre {} y {} caracteres.",
+            USERNAME_MIN_LENGTH, USERNAME_MAX_LENGTH
+        )));
+    }
+
+    let starts_with_letter = username.chars().next().is_some_and(|c| c.is_ascii_alphabetic());
+    let valid_chars = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+
+    if !starts_with_letter || !valid_chars {
+        return Err(BankError::InvalidRequest(
+            "El nombre de usuario debe empezar por una letra y solo puede contener letras, números, '.', '_' y '-'."
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn validate_password(username: &str, password: &str) -> Result<(), BankError> {
+    if password.chars().count() < PASSWORD_MIN_LENGTH {
+        return Err(BankError::WeakPassword(format!(
+            "La contraseña debe tener al menos {} caracteres.",
+            PASSWORD_MIN_LENGTH
+        )));
+    }
+
+    if password.len() > PASSWORD_MAX_BYTES {
+        return Err(BankError::WeakPassword(format!(
+            "La contraseña no puede superar los {} bytes.",
+            PASSWORD_MAX_BYTES
+        )));
+    }
+
+    let lowercase = password.to_lowercase();
+    if COMMON_PASSWORDS.contains(&lowercase.as_str()) {
+        return Err(BankError::WeakPassword("Esa contraseña es demasiado común.".to_string()));
+    }
+
+    if lowercase.contains(&username.to_lowercase()) {
+        return Err(BankError::WeakPassword(
+            "La contraseña no puede contener el nombre de usuario.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Registro abierto: crea un cliente con su cuenta y consume la invitación, si la hay
+#[instrument(skip_all, fields(username = %request.username))]
+pub async fn register(
+    store: &dyn Store,
+    config: &RegistrationConfig,
+    request: &RegisterRequest,
+) -> Result<i32, BankError> {
+    // 1. Validar el nombre y la contraseña
+    let username = request.username.trim();
+    validate_username(username)?;
+    validate_password(username, &request.password)?;
+
+    let invite_code = request.invite_code.as_deref().map(str::trim).filter(|code| !code.is_empty());
+    if invite_code.is_none() && config.require_invite_code {
+        return Err(BankError::InvalidInviteCode);
+    }
+
+    let hashed_password = bcrypt::hash(&request.password, 10)
+        .map_err(|e| BankError::Internal(format!("no se pudo generar el hash: {}", e)))?;
+
+    let mut uow = store.begin().await?;
+
+    // 2. Comprobar la invitación (bloqueada hasta confirmar)
+    let invite = match invite_code {
+        Some(code) => {
+            let invite = uow.find_invite_code(code).await?.ok_or(BankError::InvalidInviteCode)?;
+            if invite.used_by.is_some() || invite.expires_at <= Utc::now() {
+                return Err(BankError::InvalidInviteCode);
+            }
+            Some(invite)
+        }
+        None => None,
+    };
+
+    // 3. Crear el usuario y su cuenta
+    let user_id = uow.insert_user(username, &hashed_password, "user").await?;
+    uow.insert_account(user_id).await?;
+
+    // 4. Consumir la invitación y registrar el alta
+    if let Some(invite) = &invite {
+        uow.mark_invite_code_used(invite.id, user_id).await?;
+    }
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::UserCreated,
+        actor_user_id: user_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: Some(json!({
+            "role": "user",
+            "self_registered": true,
+            "invited_by": invite.as_ref().map(|invite| invite.created_by),
+        })),
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(user_id)
+}
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn create_invite(
+    store: &dyn Store,
+    config: &RegistrationConfig,
+    user_id: i32,
+) -> Result<InviteCode, BankError> {
+    let mut uow = store.begin().await?;
+
+    // Sin el bloqueo, dos peticiones a la vez cuentan las mismas invitaciones y ambas pasan
+    uow.lock_user(user_id).await?;
+
+    let now = Utc::now();
+    let active_invites = uow
+        .list_invite_codes(user_id)
+        .await?
+        .iter()
+        .filter(|invite| invite.used_by.is_none() && invite.expires_at > now)
+        .count();
+
+    if active_invites >= config.max_active_invites {
+        return Err(BankError::InvalidRequest(format!(
+            "No puedes tener más de {} invitaciones pendientes.",
+            config.max_active_invites
+        )));
+    }
+
+    let code = Alphanumeric.sample_string(&mut rand::thread_rng(), INVITE_CODE_LENGTH);
+    let invite = uow
+        .insert_invite_code(&code, user_id, now + Duration::days(config.invite_code_ttl_days))
+        .await?;
+
+    uow.commit().await?;
+
+    Ok(invite)
+}
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn list_invites(store: &dyn Store, user_id: i32) -> Result<Vec<InviteCode>, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.list_invite_codes(user_id).await
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::time::Duration;
+use sqlx::mysql::MySqlDatabaseError;
+use tracing::{warn, Instrument};
+use crate::errors::BankError;
+
+// Códigos de MySQL que indican que la unidad de trabajo puede repetirse
+const ER_LOCK_DEADLOCK: u16 = 1213;
+const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+
+const MAX_ATTEMPTS: u32 = 3;
+
+pub fn is_retryable(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(db_error) => db_error
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .map(|mysql_error| {
+                mysql_error.number() == ER_LOCK_DEADLOCK || mysql_error.number() == ER_LOCK_WAIT_TIMEOUT
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// Ejecuta la unidad de trabajo completa de nuevo si MySQL la aborta por un deadlock
+// o por agotar la espera de un bloqueo. Cada intento debe abrir su propia transacción.
+pub async fn with_deadlock_retry<T, F, Fut>(mut unit_of_work: F) -> Result<T, BankError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, BankError>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        // Cada intento es una transacción distinta; el span permite distinguirlos en el log
+        let span = tracing::debug_span!("db.transaction", attempt);
+        match unit_of_work().instrument(span).await {
+            Err(BankError::Database(e)) if is_retryable(&e) && attempt < MAX_ATTEMPTS => {
+                warn!(attempt, max_attempts = MAX_ATTEMPTS, error = %e, "conflicto de bloqueo, se reintenta");
+                tokio::time::sleep(Duration::from_millis(25 * u64::from(attempt))).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
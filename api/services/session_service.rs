@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+use utoipa::ToSchema;
+
+use crate::errors::BankError;
+use crate::repository::Store;
+
+// Vista de una sesión para su dueño; `current` marca la del token que hace la petición
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SessionSummary {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub current: bool,
+}
+
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn list_sessions(
+    store: &dyn Store,
+    user_id: i32,
+    current_session_id: &str,
+) -> Result<Vec<SessionSummary>, BankError> {
+    let mut uow = store.begin().await?;
+
+    let sessions = uow
+        .list_active_sessions(user_id)
+        .await?
+        .into_iter()
+        .map(|session| SessionSummary {
+            current: session.id == current_session_id,
+            id: session.id,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+        })
+        .collect();
+
+    Ok(sessions)
+}
+
+// Cierra una sesión del usuario y revoca sus refresh tokens; sus access tokens dejan de
+// valer en la siguiente petición
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn terminate_session(store: &dyn Store, user_id: i32, session_id: &str) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+
+    // Las sesiones de otros usuarios se tratan como inexistentes
+    uow.find_session(session_id)
+        .await?
+        .filter(|session| session.user_id == user_id && session.terminated_at.is_none())
+        .ok_or(BankError::SessionNotFound)?;
+
+    uow.revoke_refresh_token_family(session_id).await?;
+    uow.terminate_session(session_id).await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
+
+// "Cerrar sesión en todas partes", incluida la sesión actual
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn terminate_all_sessions(store: &dyn Store, user_id: i32) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.revoke_user_refresh_tokens(user_id).await?;
+    uow.terminate_user_sessions(user_id).await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
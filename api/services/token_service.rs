@@ -0,0 +1,207 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{instrument, warn};
+use uuid::Uuid;
+
+use crate::errors::BankError;
+use crate::middleware::jwt_auth::Claims;
+use crate::config::AuthConfig;
+use crate::models::{SessionClient, User};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::jwt_keys::JwtKeys;
+
+// Claves y duraciones con las que se emiten los tokens; se construye una vez al arrancar
+pub struct TokenSettings {
+    pub keys: JwtKeys,
+    pub access_token_ttl: Duration,
+    pub refresh_token_ttl: Duration,
+}
+
+impl TokenSettings {
+    pub fn from_config(auth: &AuthConfig) -> Result<TokenSettings, String> {
+        Ok(TokenSettings {
+            keys: JwtKeys::from_config(auth)?,
+            access_token_ttl: Duration::minutes(auth.access_token_ttl_minutes),
+            refresh_token_ttl: Duration::days(auth.refresh_token_ttl_days),
+        })
+    }
+}
+
+// Respuesta de login y de refresh
+#[derive(Serialize, Debug)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+}
+
+// De los tokens opacos (refresh, restablecimiento) solo se guarda su SHA-256, nunca el valor en claro
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub(crate) fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn encode_access_token(settings: &TokenSettings, user: &User, session_id: &str) -> Result<String, BankError> {
+    let claims = Claims {
+        sub: user.id,
+        exp: (Utc::now() + settings.access_token_ttl).timestamp() as u64,
+        role: user.role.clone(),
+        jti: Uuid::new_v4().to_string(),
+        ver: user.token_version,
+        sid: session_id.to_string(),
+    };
+
+    settings.keys.encode(&claims)
+}
+
+pub fn decode_access_token(settings: &TokenSettings, token: &str) -> Result<Claims, BankError> {
+    settings.keys.decode::<Claims>(token)
+}
+
+// Crea un refresh token nuevo dentro de la familia de la sesión y firma el access token que lo acompaña
+async fn issue_in_family(
+    uow: &mut dyn UnitOfWork,
+    settings: &TokenSettings,
+    user: &User,
+    family_id: &str,
+) -> Result<TokenPair, BankError> {
+    let refresh_token = generate_opaque_token();
+    let expires_at = Utc::now() + settings.refresh_token_ttl;
+
+    uow.insert_refresh_token(user.id, &hash_token(&refresh_token), family_id, expires_at)
+        .await?;
+
+    Ok(TokenPair {
+        access_token: encode_access_token(settings, user, family_id)?,
+        refresh_token,
+        expires_in: settings.access_token_ttl.num_seconds(),
+    })
+}
+
+// Cada login abre una sesión, cuyo id es también la familia de sus refresh tokens
+#[instrument(skip_all, fields(user_id = user.id))]
+pub async fn issue_tokens(
+    store: &dyn Store,
+    settings: &TokenSettings,
+    user: &User,
+    client: &SessionClient,
+) -> Result<TokenPair, BankError> {
+    let mut uow = store.begin().await?;
+
+    let session_id = Uuid::new_v4().to_string();
+    uow.insert_session(&session_id, user.id, client.user_agent.as_deref(), client.ip_address.as_deref())
+        .await?;
+    let tokens = issue_in_family(uow.as_mut(), settings, user, &session_id).await?;
+
+    uow.commit().await?;
+
+    Ok(tokens)
+}
+
+// Rota el refresh token: el usado queda revocado y se entrega otro de la misma familia.
+// Si llega un token ya revocado, alguien lo ha reutilizado y se revoca la familia entera.
+#[instrument(skip_all)]
+pub async fn refresh(store: &dyn Store, settings: &TokenSettings, refresh_token: &str) -> Result<TokenPair, BankError> {
+    let mut uow = store.begin().await?;
+
+    // 1. Buscar el token (bloqueado, para que dos rotaciones simultáneas no lo usen ambas)
+    let stored = uow
+        .find_refresh_token(&hash_token(refresh_token))
+        .await?
+        .ok_or(BankError::Unauthorized)?;
+
+    // 2. Detectar reutilización
+    if stored.revoked_at.is_some() {
+        warn!(
+            refresh_token_id = stored.id,
+            user_id = stored.user_id,
+            "refresh token reutilizado, se revoca su familia y se cierra la sesión"
+        );
+        uow.revoke_refresh_token_family(&stored.family_id).await?;
+        uow.terminate_session(&stored.family_id).await?;
+        uow.commit().await?;
+        return Err(BankError::Unauthorized);
+    }
+
+    // 3. Comprobar la expiración y que la sesión siga abierta
+    if stored.expires_at <= Utc::now() {
+        return Err(BankError::Unauthorized);
+    }
+
+    let session_open = uow
+        .find_session(&stored.family_id)
+        .await?
+        .is_some_and(|session| session.terminated_at.is_none());
+
+    if !session_open {
+        return Err(BankError::Unauthorized);
+    }
+
+    // 4. Revocar el token usado y emitir el siguiente de la familia
+    let user = uow
+        .find_user_by_id(stored.user_id)
+        .await?
+        .ok_or(BankError::Unauthorized)?;
+
+    if user.disabled {
+        return Err(BankError::UserDisabled);
+    }
+
+    uow.revoke_refresh_token(stored.id).await?;
+    uow.touch_session(&stored.family_id, Utc::now()).await?;
+    let tokens = issue_in_family(uow.as_mut(), settings, &user, &stored.family_id).await?;
+
+    uow.commit().await?;
+
+    Ok(tokens)
+}
+
+// Revoca el access token hasta su expiración y cierra su sesión; si se envía un refresh
+// token de otra sesión del usuario, también cierra esa
+#[instrument(skip_all, fields(user_id = claims.sub))]
+pub async fn logout(store: &dyn Store, claims: &Claims, refresh_token: Option<&str>) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+
+    // Las revocaciones de tokens ya expirados no protegen nada: se limpian de paso
+    uow.delete_expired_revoked_tokens(now).await?;
+
+    let expires_at: DateTime<Utc> = Utc
+        .timestamp_opt(claims.exp as i64, 0)
+        .single()
+        .unwrap_or(now);
+    uow.revoke_access_token(&claims.jti, expires_at).await?;
+    uow.revoke_refresh_token_family(&claims.sid).await?;
+    uow.terminate_session(&claims.sid).await?;
+
+    // La sesión actual se cierra aunque el refresh token enviado no sea válido
+    let other_session = match refresh_token {
+        Some(refresh_token) => match uow.find_refresh_token(&hash_token(refresh_token)).await? {
+            Some(stored) if stored.user_id == claims.sub => {
+                uow.revoke_refresh_token_family(&stored.family_id).await?;
+                uow.terminate_session(&stored.family_id).await?;
+                Ok(())
+            }
+            // Un refresh token de otro usuario no se toca
+            _ => Err(BankError::Unauthorized),
+        },
+        None => Ok(()),
+    };
+
+    uow.commit().await?;
+
+    other_session
+}
+
+pub async fn is_revoked(store: &dyn Store, jti: &str) -> Result<bool, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.is_access_token_revoked(jti).await
+}
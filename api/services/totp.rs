@@ -0,0 +1,141 @@
+// Códigos de un solo uso basados en tiempo (RFC 6238) con HMAC-SHA1, 6 dígitos y pasos de
+// 30 segundos, que es lo que esperan todas las aplicaciones de autenticación
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+pub const ISSUER: &str = "FriendBank";
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+const SECRET_BYTES: usize = 20;
+// Pasos de margen a cada lado por si el reloj del teléfono no está en hora
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    encoded
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in encoded.chars().filter(|c| *c != '=') {
+        let value = BASE32_ALPHABET.iter().position(|a| *a as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+// Secreto nuevo de 160 bits, en base32 como lo muestran las aplicaciones
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+// URI `otpauth://` que las aplicaciones leen del código QR
+pub fn provisioning_uri(secret: &str, username: &str) -> String {
+    let account: String = username
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        issuer = ISSUER,
+    )
+}
+
+fn code_at(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC acepta claves de cualquier longitud");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Truncado dinámico (RFC 4226, sección 5.3)
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]])
+        & 0x7fff_ffff;
+
+    format!("{:0width$}", binary % 10u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+// Devuelve el paso del código aceptado. Los pasos iguales o anteriores a `last_used_step`
+// se rechazan para que un código interceptado no sirva una segunda vez.
+pub fn verify(secret: &str, code: &str, now: DateTime<Utc>, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let secret = base32_decode(secret)?;
+    let current_step = now.timestamp().div_euclid(STEP_SECONDS);
+
+    (current_step - ALLOWED_DRIFT_STEPS..=current_step + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| code_at(&secret, *step) == code)
+}
+
+// Código vigente, para simular en las pruebas la aplicación del usuario
+#[cfg(test)]
+fn current_code(secret: &str, now: DateTime<Utc>) -> Option<String> {
+    let secret = base32_decode(secret)?;
+    Some(code_at(&secret, now.timestamp().div_euclid(STEP_SECONDS)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Vectores del apéndice B de la RFC 6238 (SHA1), truncados a 6 dígitos
+    #[test]
+    fn matches_the_rfc_6238_test_vectors() {
+        let secret = b"12345678901234567890";
+        assert_eq!(code_at(secret, 59 / STEP_SECONDS), "287082");
+        assert_eq!(code_at(secret, 1111111109 / STEP_SECONDS), "081804");
+        assert_eq!(code_at(secret, 1234567890 / STEP_SECONDS), "005924");
+    }
+
+    #[test]
+    fn base32_round_trips_and_codes_are_not_reusable() {
+        let secret = generate_secret();
+        assert_eq!(base32_encode(&base32_decode(&secret).unwrap()), secret);
+
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let code = current_code(&secret, now).unwrap();
+        let step = verify(&secret, &code, now, None).expect("el código vigente es válido");
+
+        assert_eq!(verify(&secret, &code, now, Some(step)), None);
+        assert_eq!(verify(&secret, "12345", now, None), None);
+    }
+}
@@ -1,127 +1,319 @@
-use sqlx::{MySql, Pool};
-use actix_web::HttpResponse;
-use crate::models::{Account, TransactionData};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
+use serde_json::json;
+use tracing::{debug, instrument};
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+use crate::models::{EntryKind, Posting, TransactionData};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::amount::validate_amount;
+use crate::services::idempotency::{self, IdempotencyKey, StoredResponse};
+use crate::services::ledger;
+use crate::services::retry::with_deadlock_retry;
 
+#[instrument(skip_all, fields(user_id = sender_user_id))]
 pub async fn process_transfer(
-    db_pool: &Pool<MySql>,
+    store: &dyn Store,
+    limits: &LimitsConfig,
     sender_user_id: i32,
-    transaction_data: &TransactionData
-) -> Result<(), HttpResponse> {
-    println!("DEBUG: Iniciando la transacción de base de datos.");
-
-    // Start a database transaction
-    let mut transaction = db_pool.begin().await.map_err(|e| {
-        println!("ERROR: Fallo al iniciar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Transacción iniciada con éxito.");
-
-    // 1. Get the account IDs and balances
-    let sender_account = sqlx::query_as!(
-        Account,
-        "SELECT id, user_id, balance FROM accounts WHERE user_id = ?",
-        sender_user_id
-    )
-    .fetch_one(&mut *transaction)
-    .await
-    .map_err(|e| {
-        println!("ERROR: Fallo al obtener la cuenta del emisor: {:?}", e);
-        HttpResponse::BadRequest().json("No se encontró la cuenta del emisor.")
-    })?;
-
-    println!("DEBUG: Cuenta del emisor encontrada: {:?}", sender_account.id);
-
-    let recipient_user = sqlx::query!(
-        "SELECT id FROM users WHERE username = ?",
-        transaction_data.recipient_username
-    )
-    .fetch_one(&mut *transaction)
-    .await
-    .map_err(|e| {
-        if let sqlx::Error::RowNotFound = e {
-            HttpResponse::BadRequest().json("El usuario receptor no existe")
-        } else {
-            println!("ERROR: Fallo al obtener el usuario receptor: {:?}", e);
-            HttpResponse::InternalServerError().finish()
-        }
-    })?;
-
-    println!("DEBUG: Usuario receptor encontrado: {:?}", recipient_user.id);
-
-    let recipient_account = sqlx::query_as!(
-        Account,
-        "SELECT id, user_id, balance FROM accounts WHERE user_id = ?",
-        recipient_user.id
-    )
-    .fetch_one(&mut *transaction)
-    .await
-    .map_err(|e| {
-        println!("ERROR: Fallo al obtener la cuenta del receptor: {:?}", e);
-        HttpResponse::InternalServerError().json("No se encontró la cuenta del receptor.")
-    })?;
-
-    println!("DEBUG: Cuenta del receptor encontrada: {:?}", recipient_account.id);
-
-    // 2. Validate the balance
-    let transaction_amount = Decimal::from_f64(transaction_data.amount)
-        .ok_or_else(|| HttpResponse::BadRequest().json("Monto inválido"))?;
-
-    println!("DEBUG: El monto de la transacción es: {:?}", transaction_amount);
+    transaction_data: &TransactionData,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    let transaction_amount = validate_amount(transaction_data.amount, limits)?;
 
+    debug!(amount = %transaction_amount, "monto validado");
+
+    let response = with_deadlock_retry(|| {
+        transfer_once(
+            store,
+            sender_user_id,
+            &transaction_data.recipient_username,
+            transaction_amount,
+            idempotency_key,
+        )
+    })
+    .await?;
+
+    Ok(response)
+}
+
+async fn transfer_once(
+    store: &dyn Store,
+    sender_user_id: i32,
+    recipient_username: &str,
+    transaction_amount: Decimal,
+    idempotency_key: Option<&IdempotencyKey>
+) -> Result<StoredResponse, BankError> {
+    // Start a unit of work (a database transaction in MySQL)
+    let mut uow = store.begin().await?;
+
+    // 0. Replay the stored response for a repeated Idempotency-Key
+    if let Some(key) = idempotency_key
+        && let Some(stored) = idempotency::find_stored_response(uow.as_mut(), sender_user_id, key).await?
+    {
+        debug!("Idempotency-Key repetida, se devuelve la respuesta guardada");
+        return Ok(stored);
+    }
+
+    // 1-5. Mover el dinero; un rechazo también se guarda para la Idempotency-Key
+    let response = match move_money(uow.as_mut(), sender_user_id, recipient_username, transaction_amount).await {
+        Ok(response) => response,
+        Err(e) => return idempotency::reject(uow, sender_user_id, idempotency_key, e).await,
+    };
+
+    if let Some(key) = idempotency_key {
+        idempotency::store_response(uow.as_mut(), sender_user_id, key, &response).await?;
+    }
+
+    // 6. Commit the unit of work
+    uow.commit().await?;
+
+    Ok(response)
+}
+
+// Todas las comprobaciones van antes de la primera escritura, así un rechazo no deja nada
+// a medias en la unidad de trabajo
+async fn move_money(
+    uow: &mut dyn UnitOfWork,
+    sender_user_id: i32,
+    recipient_username: &str,
+    transaction_amount: Decimal
+) -> Result<StoredResponse, BankError> {
+    // 1. Resolve the recipient
+    let recipient_user = uow
+        .find_user_by_username(recipient_username)
+        .await?
+        .ok_or(BankError::UnknownRecipient)?;
+
+    debug!(recipient_user_id = recipient_user.id, "receptor encontrado");
+
+    if recipient_user.id == sender_user_id {
+        return Err(BankError::SelfTransfer);
+    }
+
+    // 2. Lock both accounts, always in ascending id order so that two opposite
+    // transfers cannot deadlock each other
+    let accounts = uow.lock_accounts_by_users(&[sender_user_id, recipient_user.id]).await?;
+
+    let sender_account = accounts
+        .iter()
+        .find(|account| account.user_id == sender_user_id)
+        .ok_or(BankError::AccountNotFound)?;
+
+    let recipient_account = accounts
+        .iter()
+        .find(|account| account.user_id == recipient_user.id)
+        .ok_or(BankError::AccountNotFound)?;
+
+    debug!(
+        sender_account_id = sender_account.id,
+        recipient_account_id = recipient_account.id,
+        "cuentas bloqueadas"
+    );
+
+    // 3. Validate the balance
     if sender_account.balance < transaction_amount {
-        println!("DEBUG: Fondos insuficientes. Saldo actual: {:?}, Monto: {:?}", sender_account.balance, transaction_amount);
-        return Err(HttpResponse::BadRequest().json("Fondos insuficientes"));
-    }
-    
-    // 3. Update balances and record the transaction
-    println!("DEBUG: Actualizando el saldo del emisor.");
-    sqlx::query!(
-        "UPDATE accounts SET balance = balance - ? WHERE id = ?",
-        transaction_amount,
-        sender_account.id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| { 
-        println!("ERROR: Fallo al actualizar la cuenta del emisor: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Actualizando el saldo del receptor.");
-    sqlx::query!(
-        "UPDATE accounts SET balance = balance + ? WHERE id = ?",
-        transaction_amount,
-        recipient_account.id
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| {
-        println!("ERROR: Fallo al actualizar la cuenta del receptor: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    println!("DEBUG: Registrando la transacción en la base de datos.");
-    sqlx::query!(
-        "INSERT INTO transactions (sender_id, recipient_id, amount) VALUES (?, ?, ?)",
-        sender_account.id,
-        recipient_account.id,
-        transaction_amount
-    )
-    .execute(&mut *transaction)
-    .await.map_err(|e| { 
-        println!("ERROR: Fallo al insertar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-
-    // 4. Commit the transaction
-    println!("DEBUG: Intentando confirmar la transacción.");
-    transaction.commit().await.map_err(|e| {
-        println!("ERROR: Fallo al confirmar la transacción: {:?}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
-    
-    println!("DEBUG: ¡Transacción confirmada con éxito!");
-
-    Ok(())
+        debug!(balance = %sender_account.balance, amount = %transaction_amount, "fondos insuficientes");
+        return Err(BankError::InsufficientFunds);
+    }
+
+    // 4. Record the transaction
+    // Los movimientos se registran por ID de usuario, igual que depósitos y retiros
+    let transaction_id = uow
+        .insert_transaction(sender_user_id, recipient_user.id, transaction_amount)
+        .await?;
+
+    // 5. Post the journal entry, which also updates both balances
+    let mut postings = [
+        Posting { account_id: sender_account.id, amount: -transaction_amount },
+        Posting { account_id: recipient_account.id, amount: transaction_amount },
+    ];
+    postings.sort_by_key(|posting| posting.account_id);
+    ledger::post_entry(uow, EntryKind::Transfer, transaction_id, &postings).await?;
+
+    Ok(StoredResponse::ok(json!({
+        "message": "Transferencia realizada con éxito"
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future::join_all;
+    use rust_decimal_macros::dec;
+    use sqlx::mysql::MySqlPoolOptions;
+    use crate::models::AccountantData;
+    use crate::repository::memory::MemoryStore;
+    use crate::repository::mysql::MySqlStore;
+    use crate::services::accountant::deposit::process_deposit;
+
+    async fn create_user(store: &dyn Store, username: &str) -> i32 {
+        let mut uow = store.begin().await.unwrap();
+        let user_id = uow.insert_user(username, "", "user").await.unwrap();
+        uow.insert_account(user_id).await.unwrap();
+        uow.commit().await.unwrap();
+        user_id
+    }
+
+    // Fondea la cuenta pasando por el servicio de depósito, como haría el contador
+    async fn fund(store: &dyn Store, accountant_id: i32, username: &str, amount: Decimal) {
+        let data = AccountantData { username: username.to_string(), amount };
+        process_deposit(store, &LimitsConfig::default(), accountant_id, &data, None).await.unwrap();
+    }
+
+    async fn balance_of(store: &dyn Store, user_id: i32) -> Decimal {
+        let mut uow = store.begin().await.unwrap();
+        uow.find_account_by_user(user_id).await.unwrap().unwrap().balance
+    }
+
+    fn transfer_to(username: &str, amount: Decimal) -> TransactionData {
+        TransactionData { recipient_username: username.to_string(), amount }
+    }
+
+    #[tokio::test]
+    async fn transfer_moves_money_and_keeps_the_ledger_balanced() {
+        let store = MemoryStore::new();
+        let limits = LimitsConfig::default();
+        let accountant_id = create_user(&store, "contador").await;
+        let alice = create_user(&store, "alice").await;
+        let bob = create_user(&store, "bob").await;
+        fund(&store, accountant_id, "alice", dec!(50)).await;
+
+        process_transfer(&store, &limits, alice, &transfer_to("bob", dec!(20.25)), None).await.unwrap();
+
+        assert_eq!(balance_of(&store, alice).await, dec!(29.75));
+        assert_eq!(balance_of(&store, bob).await, dec!(20.25));
+        assert!(ledger::check_invariants(&store).await.unwrap().consistent);
+    }
+
+    #[tokio::test]
+    async fn transfer_rejects_business_rule_violations_without_moving_money() {
+        let store = MemoryStore::new();
+        let limits = LimitsConfig::default();
+        let accountant_id = create_user(&store, "contador").await;
+        let alice = create_user(&store, "alice").await;
+        let bob = create_user(&store, "bob").await;
+        fund(&store, accountant_id, "alice", dec!(10)).await;
+
+        let insufficient = process_transfer(&store, &limits, alice, &transfer_to("bob", dec!(10.01)), None).await;
+        assert!(matches!(insufficient, Err(BankError::InsufficientFunds)));
+
+        let unknown = process_transfer(&store, &limits, alice, &transfer_to("carol", dec!(1)), None).await;
+        assert!(matches!(unknown, Err(BankError::UnknownRecipient)));
+
+        let to_self = process_transfer(&store, &limits, alice, &transfer_to("alice", dec!(1)), None).await;
+        assert!(matches!(to_self, Err(BankError::SelfTransfer)));
+
+        let too_precise = process_transfer(&store, &limits, alice, &transfer_to("bob", dec!(0.001)), None).await;
+        assert!(matches!(too_precise, Err(BankError::InvalidAmount(_))));
+
+        assert_eq!(balance_of(&store, alice).await, dec!(10));
+        assert_eq!(balance_of(&store, bob).await, dec!(0));
+    }
+
+    #[tokio::test]
+    async fn repeated_idempotency_key_moves_money_once() {
+        let store = MemoryStore::new();
+        let limits = LimitsConfig::default();
+        let accountant_id = create_user(&store, "contador").await;
+        let alice = create_user(&store, "alice").await;
+        let bob = create_user(&store, "bob").await;
+        fund(&store, accountant_id, "alice", dec!(10)).await;
+
+        let data = transfer_to("bob", dec!(4));
+        let key = IdempotencyKey::new("retry-1", "transfer", &data).unwrap();
+        let first = process_transfer(&store, &limits, alice, &data, Some(&key)).await.unwrap();
+        let second = process_transfer(&store, &limits, alice, &data, Some(&key)).await.unwrap();
+
+        assert!(!first.replayed);
+        assert!(second.replayed);
+        assert_eq!(first.body, second.body);
+        assert_eq!(balance_of(&store, bob).await, dec!(4));
+
+        let other_body = transfer_to("bob", dec!(5));
+        let reused = IdempotencyKey::new("retry-1", "transfer", &other_body).unwrap();
+        let result = process_transfer(&store, &limits, alice, &other_body, Some(&reused)).await;
+        assert!(matches!(result, Err(BankError::IdempotencyKeyReused)));
+    }
+
+    #[tokio::test]
+    async fn repeated_idempotency_key_replays_a_rejection() {
+        let store = MemoryStore::new();
+        let limits = LimitsConfig::default();
+        let accountant_id = create_user(&store, "contador").await;
+        let alice = create_user(&store, "alice").await;
+        let bob = create_user(&store, "bob").await;
+
+        let data = transfer_to("bob", dec!(4));
+        let key = IdempotencyKey::new("retry-1", "transfer", &data).unwrap();
+        let first = process_transfer(&store, &limits, alice, &data, Some(&key)).await;
+        assert!(matches!(first, Err(BankError::InsufficientFunds)));
+
+        // Aunque ahora haya fondos, la misma clave repite el rechazo en lugar de transferir
+        fund(&store, accountant_id, "alice", dec!(10)).await;
+        let second = process_transfer(&store, &limits, alice, &data, Some(&key)).await.unwrap();
+
+        assert!(second.replayed);
+        assert_eq!(second.status, BankError::InsufficientFunds.problem_details().status());
+        assert_eq!(balance_of(&store, alice).await, dec!(10));
+        assert_eq!(balance_of(&store, bob).await, dec!(0));
+    }
+
+    #[tokio::test]
+    async fn accounts_are_locked_and_returned_in_ascending_id_order() {
+        let store = MemoryStore::new();
+        let alice = create_user(&store, "alice").await;
+        let bob = create_user(&store, "bob").await;
+
+        let mut uow = store.begin().await.unwrap();
+        let locked = uow.lock_accounts_by_users(&[bob, alice]).await.unwrap();
+        let ids: Vec<i32> = locked.iter().map(|account| account.id).collect();
+
+        assert_eq!(locked.len(), 2);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[tokio::test]
+    async fn concurrent_transfers_never_overdraw_the_sender_in_memory() {
+        assert_concurrent_transfers_never_overdraw(MemoryStore::new()).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "necesita DATABASE_URL apuntando a una base MySQL desechable"]
+    async fn concurrent_transfers_never_overdraw_the_sender() {
+        dotenv::dotenv().ok();
+        let db_pool = MySqlPoolOptions::new()
+            .max_connections(20)
+            .connect(&std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        crate::run_migrations(&db_pool).await.unwrap();
+        assert_concurrent_transfers_never_overdraw(MySqlStore::new(db_pool)).await;
+    }
+
+    // Mismo escenario para los dos almacenamientos: el de memoria comprueba el contrato
+    // de bloqueo y el de MySQL, los bloqueos de fila reales
+    async fn assert_concurrent_transfers_never_overdraw<S: Store + Clone>(store: S) {
+        let limits = LimitsConfig::default();
+
+        let suffix = chrono::Utc::now().timestamp_micros();
+        let accountant_id = create_user(&store, &format!("accountant_{suffix}")).await;
+        let sender_username = format!("sender_{suffix}");
+        let sender_id = create_user(&store, &sender_username).await;
+        let recipient_username = format!("recipient_{suffix}");
+        let recipient_id = create_user(&store, &recipient_username).await;
+        fund(&store, accountant_id, &sender_username, dec!(100)).await;
+
+        // 20 transferencias de 10 contra un saldo de 100: solo 10 pueden pasar
+        let transfers = (0..20).map(|_| {
+            let store = store.clone();
+            let data = transfer_to(&recipient_username, dec!(10));
+            let limits = &limits;
+            async move { process_transfer(&store, limits, sender_id, &data, None).await.is_ok() }
+        });
+        let succeeded = join_all(transfers).await.into_iter().filter(|ok| *ok).count();
+
+        assert_eq!(succeeded, 10);
+        assert_eq!(balance_of(&store, sender_id).await, dec!(0));
+        assert_eq!(balance_of(&store, recipient_id).await, dec!(100));
+    }
 }
@@ -0,0 +1,306 @@
+use bcrypt::verify;
+use chrono::{Duration, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::Serialize;
+use serde_json::json;
+use tracing::instrument;
+use utoipa::ToSchema;
+
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
+use crate::models::{AuditAction, AuditEntry, TotpSecret, User};
+use crate::repository::{Store, UnitOfWork};
+use crate::services::admin_service::ROLES;
+use crate::services::login_throttle;
+use crate::services::token_service::{generate_opaque_token, hash_token};
+use crate::services::totp;
+
+pub const CHALLENGE_TTL_MINUTES: i64 = 5;
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+// Los códigos de recuperación se comparan sin guiones ni mayúsculas
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace('-', "")
+}
+
+fn generate_recovery_code() -> String {
+    let code = Alphanumeric
+        .sample_string(&mut rand::thread_rng(), 10)
+        .to_lowercase();
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+// Acepta un código TOTP o, si no lo es, un código de recuperación sin usar
+async fn check_second_factor(
+    uow: &mut dyn UnitOfWork,
+    totp_secret: &TotpSecret,
+    code: &str,
+) -> Result<bool, BankError> {
+    if let Some(step) = totp::verify(&totp_secret.secret, code, Utc::now(), totp_secret.last_used_step) {
+        uow.update_totp_last_used_step(totp_secret.user_id, step).await?;
+        return Ok(true);
+    }
+
+    uow.use_recovery_code(totp_secret.user_id, &hash_token(&normalize_recovery_code(code)))
+        .await
+}
+
+async fn enabled_secret(uow: &mut dyn UnitOfWork, user_id: i32) -> Result<Option<TotpSecret>, BankError> {
+    Ok(uow
+        .find_totp_secret(user_id)
+        .await?
+        .filter(|totp_secret| totp_secret.enabled_at.is_some()))
+}
+
+pub async fn is_enabled(store: &dyn Store, user_id: i32) -> Result<bool, BankError> {
+    let mut uow = store.begin().await?;
+
+    uow.is_totp_enabled(user_id).await
+}
+
+// El rol del usuario exige 2FA y todavía no la ha activado
+pub async fn enrollment_required(store: &dyn Store, user: &User) -> Result<bool, BankError> {
+    let mut uow = store.begin().await?;
+
+    Ok(uow.role_requires_two_factor(&user.role).await? && !uow.is_totp_enabled(user.id).await?)
+}
+
+// Primer paso del alta: genera un secreto pendiente hasta que se confirme con un código
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn setup(store: &dyn Store, user_id: i32) -> Result<TwoFactorSetup, BankError> {
+    let mut uow = store.begin().await?;
+
+    let user = uow.find_user_by_id(user_id).await?.ok_or(BankError::Unauthorized)?;
+    if uow.is_totp_enabled(user_id).await? {
+        return Err(BankError::InvalidRequest(
+            "La verificación en dos pasos ya está activada.".to_string(),
+        ));
+    }
+
+    let secret = totp::generate_secret();
+    uow.save_pending_totp_secret(user_id, &secret).await?;
+    uow.commit().await?;
+
+    Ok(TwoFactorSetup {
+        provisioning_uri: totp::provisioning_uri(&secret, &user.username),
+        secret,
+    })
+}
+
+// Segundo paso del alta: con un código válido se activa y se entregan los códigos de
+// recuperación, que no se pueden volver a consultar
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn enable(store: &dyn Store, user_id: i32, code: &str) -> Result<Vec<String>, BankError> {
+    let mut uow = store.begin().await?;
+
+    let totp_secret = match uow.find_totp_secret(user_id).await? {
+        Some(totp_secret) if totp_secret.enabled_at.is_none() => totp_secret,
+        Some(_) => {
+            return Err(BankError::InvalidRequest(
+                "La verificación en dos pasos ya está activada.".to_string(),
+            ))
+        }
+        None => {
+            return Err(BankError::InvalidRequest(
+                "Primero hay que generar el secreto en /protected/2fa/setup.".to_string(),
+            ))
+        }
+    };
+
+    let step = totp::verify(&totp_secret.secret, code, Utc::now(), None)
+        .ok_or(BankError::InvalidTwoFactorCode)?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    let code_hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| hash_token(&normalize_recovery_code(code)))
+        .collect();
+
+    uow.enable_totp(user_id).await?;
+    uow.update_totp_last_used_step(user_id, step).await?;
+    uow.replace_recovery_codes(user_id, &code_hashes).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::TwoFactorEnabled,
+        actor_user_id: user_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: None,
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(recovery_codes)
+}
+
+// Con un token robado se podrían probar códigos sin fin: los fallos de contraseña y de
+// código cuentan contra el bloqueo del login, igual que en `complete_login`
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn disable(
+    store: &dyn Store,
+    limits: &LimitsConfig,
+    user_id: i32,
+    password: &str,
+    code: &str,
+    client_ip: Option<&str>,
+) -> Result<(), BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+
+    let user = uow.find_user_by_id(user_id).await?.ok_or(BankError::Unauthorized)?;
+
+    // 1. Rechazar el intento si el usuario o la IP están bloqueados
+    if let Some(retry_after) = login_throttle::retry_after(uow.as_mut(), &user.username, client_ip, now).await? {
+        login_throttle::record_blocked(uow.as_mut(), &user.username, client_ip, retry_after).await?;
+        uow.commit().await?;
+        return Err(BankError::TooManyAttempts(retry_after));
+    }
+
+    // 2. Comprobar la contraseña y el código
+    if !verify(password, &user.password_hash).unwrap_or(false) {
+        login_throttle::record_failure(uow.as_mut(), limits, &user.username, Some(user.id), client_ip, now).await?;
+        uow.commit().await?;
+        return Err(BankError::InvalidCredentials);
+    }
+
+    if uow.role_requires_two_factor(&user.role).await? {
+        return Err(BankError::InvalidRequest(
+            "Tu rol exige la verificación en dos pasos; no se puede desactivar.".to_string(),
+        ));
+    }
+
+    let totp_secret = enabled_secret(uow.as_mut(), user_id)
+        .await?
+        .ok_or_else(|| BankError::InvalidRequest("La verificación en dos pasos no está activada.".to_string()))?;
+
+    if !check_second_factor(uow.as_mut(), &totp_secret, code).await? {
+        login_throttle::record_failure(uow.as_mut(), limits, &user.username, Some(user.id), client_ip, now).await?;
+        uow.commit().await?;
+        return Err(BankError::InvalidTwoFactorCode);
+    }
+
+    // 3. Desactivar la 2FA; con la contraseña y el código correctos se borran los fallos
+    login_throttle::record_success(uow.as_mut(), &user.username).await?;
+    uow.delete_totp(user_id).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::TwoFactorDisabled,
+        actor_user_id: user_id,
+        target_user_id: Some(user_id),
+        amount: None,
+        details: None,
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
+
+// Desafío de corta duración que `/auth/login` entrega en lugar de los tokens
+#[instrument(skip_all, fields(user_id = user_id))]
+pub async fn create_challenge(store: &dyn Store, user_id: i32) -> Result<String, BankError> {
+    let mut uow = store.begin().await?;
+
+    let challenge = generate_opaque_token();
+    let expires_at = Utc::now() + Duration::minutes(CHALLENGE_TTL_MINUTES);
+    uow.insert_login_challenge(user_id, &hash_token(&challenge), expires_at).await?;
+
+    uow.commit().await?;
+
+    Ok(challenge)
+}
+
+// Segundo paso del login: consume el desafío si el código es correcto. Tras varios
+// intentos fallidos el desafío deja de valer y hay que volver a introducir la contraseña.
+#[instrument(skip_all)]
+pub async fn complete_login(
+    store: &dyn Store,
+    limits: &LimitsConfig,
+    challenge: &str,
+    code: &str,
+    client_ip: Option<&str>,
+) -> Result<User, BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+
+    // 1. Comprobar el desafío (bloqueado hasta confirmar)
+    let login_challenge = uow
+        .find_login_challenge(&hash_token(challenge.trim()))
+        .await?
+        .ok_or(BankError::InvalidTwoFactorCode)?;
+
+    if login_challenge.used_at.is_some()
+        || login_challenge.expires_at <= now
+        || login_challenge.failed_attempts >= MAX_CHALLENGE_ATTEMPTS
+    {
+        return Err(BankError::InvalidTwoFactorCode);
+    }
+
+    let user = uow
+        .find_user_by_id(login_challenge.user_id)
+        .await?
+        .ok_or(BankError::InvalidTwoFactorCode)?;
+
+    // 2. Rechazar el intento si el usuario o la IP están bloqueados, igual que en /auth/login
+    if let Some(retry_after) = login_throttle::retry_after(uow.as_mut(), &user.username, client_ip, now).await? {
+        login_throttle::record_blocked(uow.as_mut(), &user.username, client_ip, retry_after).await?;
+        uow.commit().await?;
+        return Err(BankError::TooManyAttempts(retry_after));
+    }
+
+    // 3. Comprobar el código; el fallo cuenta para el desafío y para el bloqueo del login
+    let totp_secret = enabled_secret(uow.as_mut(), login_challenge.user_id)
+        .await?
+        .ok_or(BankError::InvalidTwoFactorCode)?;
+
+    if !check_second_factor(uow.as_mut(), &totp_secret, code).await? {
+        uow.record_login_challenge_failure(login_challenge.id).await?;
+        login_throttle::record_failure(uow.as_mut(), limits, &user.username, Some(user.id), client_ip, now).await?;
+        uow.commit().await?;
+        return Err(BankError::InvalidTwoFactorCode);
+    }
+
+    if user.disabled {
+        return Err(BankError::UserDisabled);
+    }
+
+    // 4. Consumir el desafío; solo ahora el login está completo y se borran los fallos
+    uow.consume_login_challenge(login_challenge.id).await?;
+    login_throttle::record_success(uow.as_mut(), &user.username).await?;
+    uow.commit().await?;
+
+    Ok(user)
+}
+
+#[instrument(skip_all, fields(admin_id = admin_id, role = %role))]
+pub async fn set_role_policy(store: &dyn Store, admin_id: i32, role: &str, required: bool) -> Result<(), BankError> {
+    if !ROLES.contains(&role) {
+        return Err(BankError::InvalidRequest(format!("El rol '{}' no existe.", role)));
+    }
+
+    let mut uow = store.begin().await?;
+
+    uow.set_role_requires_two_factor(role, required).await?;
+
+    uow.insert_audit_log(&AuditEntry {
+        action: AuditAction::RolePolicyChanged,
+        actor_user_id: admin_id,
+        target_user_id: None,
+        amount: None,
+        details: Some(json!({ "role": role, "require_two_factor": required })),
+    })
+    .await?;
+
+    uow.commit().await?;
+
+    Ok(())
+}
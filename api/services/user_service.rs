@@ -1,29 +1,60 @@
-use sqlx::{MySql, Pool};
+use crate::config::LimitsConfig;
+use crate::errors::BankError;
 use crate::models::{User, UserData};
+use crate::repository::Store;
+use crate::services::login_throttle;
 use bcrypt::verify;
-use actix_web::HttpResponse;
-pub async fn verify_login(db_pool: &Pool<MySql>, user_data: &UserData) -> Result<User, HttpResponse> {
-    let user = match sqlx::query_as!(
-        User,
-        "SELECT id, username, password_hash, role FROM users WHERE username = ?",
-        user_data.username
-    )
-    .fetch_one(db_pool)
-    .await {
-        Ok(u) => u,
-        Err(sqlx::Error::RowNotFound) => {
-            return Err(HttpResponse::Unauthorized().json("Credenciales incorrectas"));
+use chrono::Utc;
+use tracing::instrument;
+
+// Hash bcrypt de una contraseña cualquiera, con el coste de las de los usuarios (10).
+// Con un usuario que no existe se comprueba la contraseña contra él para que la respuesta
+// tarde lo mismo que con una contraseña incorrecta y no delate qué usuarios existen.
+const DUMMY_PASSWORD_HASH: &str = "$2b$10$QPlg8Itb6vaNIJKAze21YeBLT7giPMasdxajAwPhLB3EVIt35jyPm";
+
+#[instrument(skip_all, fields(username = %user_data.username))]
+pub async fn verify_login(
+    store: &dyn Store,
+    limits: &LimitsConfig,
+    user_data: &UserData,
+    client_ip: Option<&str>,
+) -> Result<User, BankError> {
+    let mut uow = store.begin().await?;
+    let now = Utc::now();
+
+    // 1. Rechazar el intento si el usuario o la IP están bloqueados
+    if let Some(retry_after) = login_throttle::retry_after(uow.as_mut(), &user_data.username, client_ip, now).await? {
+        login_throttle::record_blocked(uow.as_mut(), &user_data.username, client_ip, retry_after).await?;
+        uow.commit().await?;
+        return Err(BankError::TooManyAttempts(retry_after));
+    }
+
+    // 2. Comprobar la contraseña
+    let user = uow.find_user_by_username(&user_data.username).await?;
+
+    let password_hash = user.as_ref().map_or(DUMMY_PASSWORD_HASH, |user| user.password_hash.as_str());
+    let is_password_valid = verify(&user_data.password, password_hash).unwrap_or(false) && user.is_some();
+
+    // 3. Contar el fallo, también para usuarios que no existen
+    let user = match user {
+        Some(user) if is_password_valid => user,
+        unknown_or_wrong => {
+            let user_id = unknown_or_wrong.map(|user| user.id);
+            login_throttle::record_failure(uow.as_mut(), limits, &user_data.username, user_id, client_ip, now).await?;
+            uow.commit().await?;
+            return Err(BankError::InvalidCredentials);
         }
-        Err(_) => return Err(HttpResponse::InternalServerError().finish()),
     };
 
-    let is_password_valid = match verify(&user_data.password, &user.password_hash) {
-        Ok(valid) => valid,
-        Err(_) => false,
-    };
+    // Con 2FA activada los fallos se borran cuando el segundo factor también es correcto
+    if !uow.is_totp_enabled(user.id).await? {
+        login_throttle::record_success(uow.as_mut(), &user.username).await?;
+    }
+    uow.commit().await?;
 
-    if !is_password_valid {
-        return Err(HttpResponse::Unauthorized().json("Credenciales incorrectas"));
+    // Solo se revela que está deshabilitado a quien conoce la contraseña
+    if user.disabled {
+        return Err(BankError::UserDisabled);
     }
 
     Ok(user)
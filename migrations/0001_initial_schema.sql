@@ -0,0 +1,64 @@
+-- Esquema inicial de FriendBank
+
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    username VARCHAR(64) NOT NULL,
+    password_hash VARCHAR(255) NOT NULL,
+    role VARCHAR(32) NOT NULL DEFAULT 'user',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_users_username UNIQUE (username)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE accounts (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    balance DECIMAL(19, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_accounts_user_id UNIQUE (user_id),
+    CONSTRAINT fk_accounts_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- sender_id y recipient_id son IDs de usuario (el contador participa en depósitos y retiros)
+CREATE TABLE transactions (
+    id INT NOT NULL AUTO_INCREMENT,
+    sender_id INT NOT NULL,
+    recipient_id INT NOT NULL,
+    amount DECIMAL(19, 2) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX idx_transactions_sender (sender_id, id),
+    INDEX idx_transactions_recipient (recipient_id, id),
+    CONSTRAINT fk_transactions_sender FOREIGN KEY (sender_id) REFERENCES users (id),
+    CONSTRAINT fk_transactions_recipient FOREIGN KEY (recipient_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE audit_log (
+    id INT NOT NULL AUTO_INCREMENT,
+    amount DECIMAL(19, 2) NOT NULL,
+    type ENUM('deposit', 'withdrawal') NOT NULL,
+    accountant_user_id INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_audit_log_accountant FOREIGN KEY (accountant_user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Tablas de una sola fila
+CREATE TABLE total_supply (
+    id TINYINT NOT NULL DEFAULT 1,
+    total_amount DECIMAL(19, 2) NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_total_supply_singleton CHECK (id = 1)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE transaction_count (
+    id TINYINT NOT NULL DEFAULT 1,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_transaction_count_singleton CHECK (id = 1)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+INSERT INTO total_supply (id, total_amount) VALUES (1, 0);
+INSERT INTO transaction_count (id, count) VALUES (1, 0);
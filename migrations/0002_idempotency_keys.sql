@@ -0,0 +1,14 @@
+-- Respuestas guardadas por Idempotency-Key para transferencias, depósitos y retiros
+
+CREATE TABLE idempotency_keys (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    response_status SMALLINT UNSIGNED NOT NULL,
+    response_body JSON NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_idempotency_keys_user_key UNIQUE (user_id, idempotency_key),
+    CONSTRAINT fk_idempotency_keys_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
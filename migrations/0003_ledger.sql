@@ -0,0 +1,45 @@
+-- Libro mayor de partida doble
+
+-- La cuenta de reserva del banco es la contrapartida de depósitos y retiros; no pertenece a ningún usuario
+ALTER TABLE accounts
+    MODIFY user_id INT NULL,
+    ADD COLUMN kind ENUM('customer', 'reserve') NOT NULL DEFAULT 'customer' AFTER user_id;
+
+INSERT INTO accounts (user_id, kind, balance) VALUES (NULL, 'reserve', 0);
+
+CREATE TABLE journal_entries (
+    id INT NOT NULL AUTO_INCREMENT,
+    kind ENUM('opening', 'transfer', 'deposit', 'withdrawal') NOT NULL,
+    transaction_id INT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_journal_entries_transaction FOREIGN KEY (transaction_id) REFERENCES transactions (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Importe con signo: positivo aumenta el saldo de la cuenta, negativo lo disminuye.
+-- Los apuntes de cada asiento suman cero.
+CREATE TABLE postings (
+    id INT NOT NULL AUTO_INCREMENT,
+    journal_entry_id INT NOT NULL,
+    account_id INT NOT NULL,
+    amount DECIMAL(19, 2) NOT NULL,
+    PRIMARY KEY (id),
+    INDEX idx_postings_account (account_id),
+    CONSTRAINT fk_postings_journal_entry FOREIGN KEY (journal_entry_id) REFERENCES journal_entries (id),
+    CONSTRAINT fk_postings_account FOREIGN KEY (account_id) REFERENCES accounts (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Asiento de apertura con los saldos existentes, contra la reserva
+SELECT id INTO @reserve_account_id FROM accounts WHERE kind = 'reserve';
+SELECT COALESCE(SUM(balance), 0) INTO @opening_supply FROM accounts WHERE kind = 'customer';
+
+INSERT INTO journal_entries (kind) VALUES ('opening');
+SET @opening_entry_id = LAST_INSERT_ID();
+
+INSERT INTO postings (journal_entry_id, account_id, amount)
+SELECT @opening_entry_id, id, balance FROM accounts WHERE kind = 'customer' AND balance <> 0;
+
+INSERT INTO postings (journal_entry_id, account_id, amount)
+VALUES (@opening_entry_id, @reserve_account_id, -@opening_supply);
+
+UPDATE accounts SET balance = -@opening_supply WHERE id = @reserve_account_id;
@@ -0,0 +1,24 @@
+-- Refresh tokens rotativos y revocación de access tokens
+
+-- Solo se guarda el SHA-256 del refresh token. Todos los tokens obtenidos por rotación
+-- a partir del mismo login comparten `family_id`.
+CREATE TABLE refresh_tokens (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    family_id CHAR(36) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_refresh_tokens_hash UNIQUE (token_hash),
+    INDEX idx_refresh_tokens_family (family_id),
+    CONSTRAINT fk_refresh_tokens_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Access tokens revocados antes de expirar (por `jti`), hasta su fecha de expiración
+CREATE TABLE revoked_tokens (
+    jti CHAR(36) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (jti)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,34 @@
+-- Permisos y su asignación a roles
+
+CREATE TABLE permissions (
+    name VARCHAR(64) NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    PRIMARY KEY (name)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- `role` es el mismo texto que `users.role`
+CREATE TABLE role_permissions (
+    role VARCHAR(32) NOT NULL,
+    permission VARCHAR(64) NOT NULL,
+    PRIMARY KEY (role, permission),
+    CONSTRAINT fk_role_permissions_permission FOREIGN KEY (permission) REFERENCES permissions (name)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+INSERT INTO permissions (name, description) VALUES
+    ('transfer:create', 'Transferir dinero a otro cliente'),
+    ('account:read', 'Consultar la cuenta y el historial propios'),
+    ('account:read_any', 'Consultar la cuenta de cualquier cliente'),
+    ('cash:deposit', 'Ingresar efectivo en una cuenta'),
+    ('cash:withdraw', 'Retirar efectivo de una cuenta'),
+    ('audit:read', 'Revisar la consistencia del libro mayor'),
+    ('users:manage', 'Gestionar usuarios y roles');
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('user', 'transfer:create'),
+    ('user', 'account:read'),
+    ('accountant', 'transfer:create'),
+    ('accountant', 'account:read'),
+    ('accountant', 'account:read_any'),
+    ('accountant', 'cash:deposit'),
+    ('accountant', 'cash:withdraw'),
+    ('accountant', 'audit:read');
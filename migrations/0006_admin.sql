@@ -0,0 +1,21 @@
+-- Rol de administrador, usuarios deshabilitados y log de auditoría general
+
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- El log deja de ser solo de movimientos de efectivo: registra quién hizo qué, sobre quién
+ALTER TABLE audit_log DROP FOREIGN KEY fk_audit_log_accountant;
+ALTER TABLE audit_log RENAME COLUMN type TO action;
+ALTER TABLE audit_log RENAME COLUMN accountant_user_id TO actor_user_id;
+ALTER TABLE audit_log
+    MODIFY action VARCHAR(32) NOT NULL,
+    MODIFY amount DECIMAL(19, 2) NULL,
+    ADD COLUMN target_user_id INT NULL AFTER actor_user_id,
+    ADD COLUMN details JSON NULL AFTER target_user_id,
+    ADD INDEX idx_audit_log_action (action),
+    ADD CONSTRAINT fk_audit_log_actor FOREIGN KEY (actor_user_id) REFERENCES users (id),
+    ADD CONSTRAINT fk_audit_log_target FOREIGN KEY (target_user_id) REFERENCES users (id);
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('admin', 'users:manage'),
+    ('admin', 'account:read_any'),
+    ('admin', 'audit:read');
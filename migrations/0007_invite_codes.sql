@@ -0,0 +1,25 @@
+-- Códigos de invitación para el registro abierto
+
+-- Cada código sirve una sola vez y caduca
+CREATE TABLE invite_codes (
+    id INT NOT NULL AUTO_INCREMENT,
+    code VARCHAR(32) NOT NULL,
+    created_by INT NOT NULL,
+    used_by INT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_invite_codes_code UNIQUE (code),
+    INDEX idx_invite_codes_created_by (created_by),
+    CONSTRAINT fk_invite_codes_created_by FOREIGN KEY (created_by) REFERENCES users (id),
+    CONSTRAINT fk_invite_codes_used_by FOREIGN KEY (used_by) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+INSERT INTO permissions (name, description) VALUES
+    ('invites:create', 'Invitar a nuevos miembros');
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('user', 'invites:create'),
+    ('accountant', 'invites:create'),
+    ('admin', 'invites:create');
@@ -0,0 +1,19 @@
+-- Cambio y restablecimiento de contraseña
+
+-- Se incrementa al cambiar la contraseña; los access tokens emitidos con una versión
+-- anterior dejan de ser válidos
+ALTER TABLE users ADD COLUMN token_version INT NOT NULL DEFAULT 0;
+
+-- Solo se guarda el SHA-256 del token enviado al usuario
+CREATE TABLE password_resets (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_password_resets_token_hash UNIQUE (token_hash),
+    INDEX idx_password_resets_user (user_id),
+    CONSTRAINT fk_password_resets_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,44 @@
+-- Autenticación en dos pasos (TOTP, RFC 6238)
+
+-- Secreto TOTP en base32. `enabled_at` queda a NULL hasta que el usuario confirma un código;
+-- `last_used_step` impide reutilizar un código ya aceptado
+CREATE TABLE user_totp (
+    user_id INT NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    enabled_at TIMESTAMP NULL,
+    last_used_step BIGINT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id),
+    CONSTRAINT fk_user_totp_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Códigos de recuperación de un solo uso (solo el SHA-256)
+CREATE TABLE recovery_codes (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    code_hash CHAR(64) NOT NULL,
+    used_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    INDEX idx_recovery_codes_user (user_id),
+    CONSTRAINT fk_recovery_codes_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Segundo paso pendiente de un login con contraseña correcta
+CREATE TABLE login_challenges (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    failed_attempts INT NOT NULL DEFAULT 0,
+    used_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_login_challenges_token_hash UNIQUE (token_hash),
+    CONSTRAINT fk_login_challenges_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Políticas por rol que decide un administrador
+CREATE TABLE role_policies (
+    role VARCHAR(32) NOT NULL,
+    require_two_factor BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (role)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,27 @@
+-- Protección del login contra fuerza bruta
+
+-- Fallos de login recientes por nombre de usuario (`username`) y por IP (`ip`).
+-- `locked_until` marca hasta cuándo se rechazan los intentos de ese sujeto
+CREATE TABLE login_attempts (
+    scope VARCHAR(16) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    failures INT NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    locked_until TIMESTAMP NULL,
+    PRIMARY KEY (scope, subject)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Eventos de autenticación: fallos, intentos rechazados y bloqueos.
+-- El usuario puede no existir, por eso se guarda el nombre tal como llegó
+CREATE TABLE auth_audit_log (
+    id INT NOT NULL AUTO_INCREMENT,
+    event VARCHAR(32) NOT NULL,
+    username VARCHAR(255) NULL,
+    user_id INT NULL,
+    ip_address VARCHAR(45) NULL,
+    details JSON NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX idx_auth_audit_log_created_at (created_at),
+    CONSTRAINT fk_auth_audit_log_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,16 @@
+-- Sesiones abiertas con cada login
+
+-- `id` es también el `family_id` de los refresh tokens de la sesión y va en el claim `sid`
+-- de sus access tokens. Una sesión cerrada (`terminated_at`) invalida ambos
+CREATE TABLE sessions (
+    id CHAR(36) NOT NULL,
+    user_id INT NOT NULL,
+    user_agent VARCHAR(255) NULL,
+    ip_address VARCHAR(45) NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    terminated_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    INDEX idx_sessions_user (user_id),
+    CONSTRAINT fk_sessions_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
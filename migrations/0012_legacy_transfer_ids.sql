@@ -0,0 +1,75 @@
+-- Transferencias anteriores a las migraciones
+
+-- Antes de las migraciones, las transferencias guardaban IDs de cuenta en sender_id y
+-- recipient_id, mientras que depósitos y retiros ya guardaban IDs de usuario. Esas filas
+-- no tienen asiento en el libro mayor (0003 abrió el libro con los saldos). Una fila solo
+-- puede ser una transferencia si sus dos IDs son cuentas de cliente, y solo puede ser un
+-- depósito o un retiro si hay en audit_log una entrada del mismo contador y el mismo monto.
+-- Las que cumplen lo primero y no lo segundo son transferencias seguras y se pasan a IDs de
+-- usuario. Las que cumplen las dos cosas no se tocan: quedan en
+-- `legacy_transactions_review` para revisarlas a mano.
+CREATE TABLE legacy_transactions_review (
+    transaction_id INT NOT NULL,
+    reason VARCHAR(255) NOT NULL,
+    PRIMARY KEY (transaction_id),
+    CONSTRAINT fk_legacy_review_transaction FOREIGN KEY (transaction_id) REFERENCES transactions (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TEMPORARY TABLE legacy_transfer_candidates (
+    transaction_id INT NOT NULL,
+    sender_user_id INT NOT NULL,
+    recipient_user_id INT NOT NULL,
+    matches_audit BOOLEAN NOT NULL,
+    PRIMARY KEY (transaction_id)
+);
+
+INSERT INTO legacy_transfer_candidates (transaction_id, sender_user_id, recipient_user_id, matches_audit)
+SELECT t.id, sender.user_id, recipient.user_id,
+    EXISTS (
+        SELECT 1 FROM audit_log a
+        WHERE a.action = 'deposit' AND a.actor_user_id = t.sender_id AND a.amount = t.amount
+    ) OR EXISTS (
+        SELECT 1 FROM audit_log a
+        WHERE a.action = 'withdrawal' AND a.actor_user_id = t.recipient_id AND a.amount = t.amount
+    )
+FROM transactions t
+JOIN accounts sender ON sender.id = t.sender_id AND sender.kind = 'customer'
+JOIN accounts recipient ON recipient.id = t.recipient_id AND recipient.kind = 'customer'
+WHERE NOT EXISTS (SELECT 1 FROM journal_entries j WHERE j.transaction_id = t.id);
+
+INSERT INTO legacy_transactions_review (transaction_id, reason)
+SELECT transaction_id, 'puede ser una transferencia o un depósito/retiro de audit_log'
+FROM legacy_transfer_candidates
+WHERE matches_audit;
+
+UPDATE transactions t
+JOIN legacy_transfer_candidates c ON c.transaction_id = t.id AND NOT c.matches_audit
+SET t.sender_id = c.sender_user_id,
+    t.recipient_id = c.recipient_user_id;
+
+DROP TEMPORARY TABLE legacy_transfer_candidates;
+
+-- Con los datos corregidos ya se pueden añadir las claves foráneas que la adopción de la
+-- base antigua no creó (en una base nueva ya están). Si quedan filas por revisar, sus IDs
+-- pueden no ser de usuario: las claves se añaden a mano después de la revisión.
+SET @pending_review = (SELECT COUNT(*) FROM legacy_transactions_review);
+
+SET @ddl = IF(
+    @pending_review = 0 AND (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'transactions' AND constraint_name = 'fk_transactions_sender') = 0,
+    'ALTER TABLE transactions ADD CONSTRAINT fk_transactions_sender FOREIGN KEY (sender_id) REFERENCES users (id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    @pending_review = 0 AND (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'transactions' AND constraint_name = 'fk_transactions_recipient') = 0,
+    'ALTER TABLE transactions ADD CONSTRAINT fk_transactions_recipient FOREIGN KEY (recipient_id) REFERENCES users (id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
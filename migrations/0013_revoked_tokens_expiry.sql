@@ -0,0 +1,4 @@
+-- Limpieza de access tokens revocados
+
+-- El logout borra las revocaciones ya expiradas; el índice evita recorrer toda la tabla
+ALTER TABLE revoked_tokens ADD INDEX idx_revoked_tokens_expires_at (expires_at);
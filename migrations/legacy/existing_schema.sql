@@ -0,0 +1,186 @@
+-- Adopción de una base de datos anterior a las migraciones
+
+-- Esas bases ya tienen las tablas de 0001, así que 0001 no se puede aplicar sobre ellas.
+-- En su lugar `run_migrations` ejecuta este script una sola vez y registra 0001 como
+-- aplicada. Cada tabla se crea solo si falta, y lo que el esquema antiguo podía no tener
+-- (columnas, índices, restricciones y las filas únicas) se añade comprobando antes si
+-- existe. Las claves foráneas de `transactions` se añaden en 0012, después de corregir
+-- los datos.
+
+CREATE TABLE IF NOT EXISTS users (
+    id INT NOT NULL AUTO_INCREMENT,
+    username VARCHAR(64) NOT NULL,
+    password_hash VARCHAR(255) NOT NULL,
+    role VARCHAR(32) NOT NULL DEFAULT 'user',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_users_username UNIQUE (username)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS accounts (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    balance DECIMAL(19, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_accounts_user_id UNIQUE (user_id),
+    CONSTRAINT fk_accounts_user FOREIGN KEY (user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- sender_id y recipient_id son IDs de usuario (el contador participa en depósitos y retiros)
+CREATE TABLE IF NOT EXISTS transactions (
+    id INT NOT NULL AUTO_INCREMENT,
+    sender_id INT NOT NULL,
+    recipient_id INT NOT NULL,
+    amount DECIMAL(19, 2) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX idx_transactions_sender (sender_id, id),
+    INDEX idx_transactions_recipient (recipient_id, id),
+    CONSTRAINT fk_transactions_sender FOREIGN KEY (sender_id) REFERENCES users (id),
+    CONSTRAINT fk_transactions_recipient FOREIGN KEY (recipient_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INT NOT NULL AUTO_INCREMENT,
+    amount DECIMAL(19, 2) NOT NULL,
+    type ENUM('deposit', 'withdrawal') NOT NULL,
+    accountant_user_id INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_audit_log_accountant FOREIGN KEY (accountant_user_id) REFERENCES users (id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Tablas de una sola fila
+CREATE TABLE IF NOT EXISTS total_supply (
+    id TINYINT NOT NULL DEFAULT 1,
+    total_amount DECIMAL(19, 2) NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_total_supply_singleton CHECK (id = 1)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE IF NOT EXISTS transaction_count (
+    id TINYINT NOT NULL DEFAULT 1,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    CONSTRAINT chk_transaction_count_singleton CHECK (id = 1)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Lo que puede faltar en una base de datos anterior. MySQL no tiene `ADD ... IF NOT EXISTS`:
+-- cada sentencia se prepara solo si information_schema dice que hace falta.
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.columns
+        WHERE table_schema = DATABASE() AND table_name = 'users' AND column_name = 'created_at') = 0,
+    'ALTER TABLE users ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'users' AND constraint_name = 'uq_users_username') = 0,
+    'ALTER TABLE users ADD CONSTRAINT uq_users_username UNIQUE (username)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.columns
+        WHERE table_schema = DATABASE() AND table_name = 'accounts' AND column_name = 'created_at') = 0,
+    'ALTER TABLE accounts ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.columns
+        WHERE table_schema = DATABASE() AND table_name = 'accounts' AND column_name = 'updated_at') = 0,
+    'ALTER TABLE accounts ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'accounts' AND constraint_name = 'uq_accounts_user_id') = 0,
+    'ALTER TABLE accounts ADD CONSTRAINT uq_accounts_user_id UNIQUE (user_id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'accounts' AND constraint_name = 'fk_accounts_user') = 0,
+    'ALTER TABLE accounts ADD CONSTRAINT fk_accounts_user FOREIGN KEY (user_id) REFERENCES users (id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.columns
+        WHERE table_schema = DATABASE() AND table_name = 'transactions' AND column_name = 'created_at') = 0,
+    'ALTER TABLE transactions ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.statistics
+        WHERE table_schema = DATABASE() AND table_name = 'transactions' AND index_name = 'idx_transactions_sender') = 0,
+    'ALTER TABLE transactions ADD INDEX idx_transactions_sender (sender_id, id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.statistics
+        WHERE table_schema = DATABASE() AND table_name = 'transactions' AND index_name = 'idx_transactions_recipient') = 0,
+    'ALTER TABLE transactions ADD INDEX idx_transactions_recipient (recipient_id, id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.columns
+        WHERE table_schema = DATABASE() AND table_name = 'audit_log' AND column_name = 'created_at') = 0,
+    'ALTER TABLE audit_log ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+-- 0006 la sustituye por fk_audit_log_actor y cuenta con que exista
+SET @ddl = IF(
+    (SELECT COUNT(*) FROM information_schema.table_constraints
+        WHERE table_schema = DATABASE() AND table_name = 'audit_log' AND constraint_name = 'fk_audit_log_accountant') = 0,
+    'ALTER TABLE audit_log ADD CONSTRAINT fk_audit_log_accountant FOREIGN KEY (accountant_user_id) REFERENCES users (id)',
+    'DO 0'
+);
+PREPARE stmt FROM @ddl;
+EXECUTE stmt;
+DEALLOCATE PREPARE stmt;
+
+-- Las filas únicas se conservan si ya existen, con la oferta y el contador acumulados
+INSERT INTO total_supply (total_amount) SELECT 0 FROM DUAL WHERE NOT EXISTS (SELECT 1 FROM total_supply);
+INSERT INTO transaction_count (count) SELECT 0 FROM DUAL WHERE NOT EXISTS (SELECT 1 FROM transaction_count);
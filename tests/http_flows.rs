@@ -0,0 +1,1478 @@
+// Pruebas de punta a punta de la API HTTP contra el almacenamiento en memoria
+
+use std::sync::Arc;
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use chrono::Utc;
+use futures_util::future::join_all;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use vercel_runtime::{Body, Request};
+
+use friendbank::config::{Config, Environment};
+use friendbank::errors::BankError;
+use friendbank::middleware::jwt_auth::Claims;
+use friendbank::notifier::{MemoryNotifier, Notification, Notifier};
+use friendbank::repository::memory::MemoryStore;
+use friendbank::repository::{Store, UnitOfWork};
+use friendbank::serverless::ServerlessApp;
+use friendbank::{build_app, AppState};
+
+const JWT_SECRET: &str = "secreto-solo-para-pruebas";
+const PASSWORD: &str = "contraseña-de-prueba";
+const METRICS_TOKEN: &str = "prometheus-scraper-0123456789abcdef";
+
+// Valores por defecto, sin leer el entorno ni ningún fichero
+fn test_config() -> Config {
+    let mut config = Config::default();
+    config.auth.jwt_secret = Some(JWT_SECRET.to_string());
+    config
+}
+
+macro_rules! init_app {
+    ($store:expr) => {
+        init_app!($store, MemoryNotifier::new())
+    };
+    ($store:expr, $notifier:expr) => {
+        init_app!($store, $notifier, test_config())
+    };
+    ($store:expr, $notifier:expr, $config:expr) => {
+        test::init_service(build_app(
+            AppState::new(Arc::new($store.clone()), Arc::new($notifier.clone()), $config).unwrap(),
+        ))
+        .await
+    };
+}
+
+async fn create_user(store: &MemoryStore, username: &str, role: &str) -> i32 {
+    let mut uow = store.begin().await.unwrap();
+    let user_id = uow
+        .insert_user(username, &bcrypt::hash(PASSWORD, 4).unwrap(), role)
+        .await
+        .unwrap();
+    uow.insert_account(user_id).await.unwrap();
+    uow.commit().await.unwrap();
+    user_id
+}
+
+// Abre una sesión como haría el login y firma un token para ella
+async fn token_for(store: &MemoryStore, user_id: i32, role: &str, expires_in: chrono::Duration) -> String {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let mut uow = store.begin().await.unwrap();
+    uow.insert_session(&session_id, user_id, None, None).await.unwrap();
+    uow.commit().await.unwrap();
+
+    let claims = Claims {
+        sub: user_id,
+        exp: (Utc::now() + expires_in).timestamp() as u64,
+        role: role.to_string(),
+        jti: uuid::Uuid::new_v4().to_string(),
+        ver: 0,
+        sid: session_id,
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_SECRET.as_ref())).unwrap()
+}
+
+fn bearer(token: &str) -> (&'static str, String) {
+    ("Authorization", format!("Bearer {}", token))
+}
+
+// Código TOTP vigente (RFC 6238: SHA1, 6 dígitos, pasos de 30 s), como lo calcularía la
+// aplicación de autenticación a partir del secreto en base32
+fn totp_code(secret: &str) -> String {
+    use hmac::{Hmac, Mac};
+
+    let mut key = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in secret.chars() {
+        let value = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567".iter().position(|a| *a as char == c).unwrap();
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            key.push((buffer >> bits) as u8);
+        }
+    }
+
+    let mut mac = Hmac::<sha1::Sha1>::new_from_slice(&key).unwrap();
+    mac.update(&Utc::now().timestamp().div_euclid(30).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(digest[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    format!("{:06}", binary % 1_000_000)
+}
+
+// Banco con un contador y dos clientes; alice empieza con 100
+struct Fixture {
+    store: MemoryStore,
+    accountant_token: String,
+    admin_token: String,
+    alice_token: String,
+    bob_id: i32,
+}
+
+async fn fixture() -> Fixture {
+    let store = MemoryStore::new();
+    let accountant_id = create_user(&store, "contador", "accountant").await;
+    let alice_id = create_user(&store, "alice", "user").await;
+    let bob_id = create_user(&store, "bob", "user").await;
+    let admin_id = create_user(&store, "admin", "admin").await;
+
+    let accountant_token = token_for(&store, accountant_id, "accountant", chrono::Duration::hours(1)).await;
+    let admin_token = token_for(&store, admin_id, "admin", chrono::Duration::hours(1)).await;
+    let alice_token = token_for(&store, alice_id, "user", chrono::Duration::hours(1)).await;
+
+    let app = init_app!(store);
+    let req = test::TestRequest::post()
+        .uri("/accountant/deposit")
+        .insert_header(bearer(&accountant_token))
+        .set_json(json!({ "username": "alice", "amount": "100.00" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    Fixture { store, accountant_token, admin_token, alice_token, bob_id }
+}
+
+#[actix_web::test]
+async fn login_issues_a_token_and_rejects_a_wrong_password() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["role"], "user");
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&token))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["balance"], "100.00");
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": "otra" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "INVALID_CREDENTIALS");
+}
+
+#[actix_web::test]
+async fn signup_is_reserved_to_admins_and_creates_a_user_that_can_log_in() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+    let new_user = json!({ "username": "carol", "password": "sol-de-mediodia" });
+
+    let req = test::TestRequest::post()
+        .uri("/protected/signup")
+        .set_json(&new_user)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/signup")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(&new_user)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/signup")
+        .insert_header(bearer(&f.admin_token))
+        .set_json(&new_user)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    // Misma respuesta que /admin/accountants: 201 con el id del usuario creado
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["user_id"].is_i64(), "{}", body);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(&new_user)
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn transfer_moves_money_between_customers() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/transfer")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(json!({ "recipient_username": "bob", "amount": 30.5 }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let mut uow = f.store.begin().await.unwrap();
+    let bob_account = uow.find_account_by_user(f.bob_id).await.unwrap().unwrap();
+    assert_eq!(bob_account.balance.to_string(), "30.50");
+}
+
+#[actix_web::test]
+async fn transfer_reports_insufficient_funds_and_unknown_recipients() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let cases = [
+        (json!({ "recipient_username": "bob", "amount": "100.01" }), "INSUFFICIENT_FUNDS"),
+        (json!({ "recipient_username": "nadie", "amount": "1" }), "UNKNOWN_RECIPIENT"),
+        (json!({ "recipient_username": "bob", "amount": "0" }), "INVALID_AMOUNT"),
+        (json!({ "recipient_username": "bob", "amount": "NaN" }), "INVALID_REQUEST"),
+    ];
+
+    for (payload, code) in cases {
+        let req = test::TestRequest::post()
+            .uri("/protected/transfer")
+            .insert_header(bearer(&f.alice_token))
+            .set_json(&payload)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", payload);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], code, "{}", payload);
+    }
+}
+
+#[actix_web::test]
+async fn accountant_endpoints_are_forbidden_for_customers() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    for uri in ["/accountant/deposit", "/accountant/withdraw"] {
+        let req = test::TestRequest::post()
+            .uri(uri)
+            .insert_header(bearer(&f.alice_token))
+            .set_json(json!({ "username": "alice", "amount": "10" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN, "{}", uri);
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn admin_and_accountant_scopes_require_their_permission_on_every_route() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    // Una ruta sin handler dentro del scope ya exige el permiso del scope
+    for uri in ["/admin/no-existe", "/accountant/no-existe"] {
+        let req = test::TestRequest::get().uri(uri).insert_header(bearer(&f.alice_token)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN, "{}", uri);
+    }
+    let req = test::TestRequest::get().uri("/admin/no-existe").insert_header(bearer(&f.admin_token)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    // El administrador revisa el libro, pero no mueve efectivo
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.admin_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::post()
+        .uri("/accountant/deposit")
+        .insert_header(bearer(&f.admin_token))
+        .set_json(json!({ "username": "alice", "amount": "10" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn roles_without_permissions_are_forbidden() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+    let visitor_id = create_user(&f.store, "visitante", "visitor").await;
+    let token = token_for(&f.store, visitor_id, "visitor", chrono::Duration::hours(1)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&token))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "FORBIDDEN");
+}
+
+#[actix_web::test]
+async fn accountant_withdraws_cash_but_not_beyond_the_balance() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/accountant/withdraw")
+        .insert_header(bearer(&f.accountant_token))
+        .set_json(json!({ "username": "alice", "amount": "40" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::post()
+        .uri("/accountant/withdraw")
+        .insert_header(bearer(&f.accountant_token))
+        .set_json(json!({ "username": "alice", "amount": "60.01" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["code"], "INSUFFICIENT_FUNDS");
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["consistent"], true);
+    assert_eq!(body["total_supply"], "60.00");
+}
+
+#[actix_web::test]
+async fn expired_tokens_are_rejected() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+    let expired = token_for(&f.store, f.bob_id, "user", -chrono::Duration::hours(1)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&expired))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn refresh_tokens_rotate_and_reuse_revokes_the_family() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let first_refresh = body["refresh_token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": first_refresh }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let second_refresh = body["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(first_refresh, second_refresh);
+
+    // Reutilizar el primero revoca también el segundo
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": first_refresh }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": second_refresh }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn logout_revokes_the_access_and_refresh_tokens() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let token = body["token"].as_str().unwrap().to_string();
+    let refresh_token = body["refresh_token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .insert_header(bearer(&token))
+        .set_json(json!({ "refresh_token": refresh_token }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": refresh_token }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn logout_with_a_foreign_refresh_token_still_ends_the_current_session() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let mut uow = f.store.begin().await.unwrap();
+    uow.revoke_access_token("jti-expirado", Utc::now() - chrono::Duration::minutes(1)).await.unwrap();
+    uow.commit().await.unwrap();
+
+    let mut tokens = Vec::new();
+    for username in ["alice", "bob"] {
+        let req = test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": username, "password": PASSWORD }))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+        tokens.push((
+            body["token"].as_str().unwrap().to_string(),
+            body["refresh_token"].as_str().unwrap().to_string(),
+        ));
+    }
+    let (alice_token, _) = &tokens[0];
+    let (_, bob_refresh) = &tokens[1];
+
+    let req = test::TestRequest::post()
+        .uri("/auth/logout")
+        .insert_header(bearer(alice_token))
+        .set_json(json!({ "refresh_token": bob_refresh }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    // La sesión de bob sigue abierta
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": bob_refresh }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let mut uow = f.store.begin().await.unwrap();
+    assert!(!uow.is_access_token_revoked("jti-expirado").await.unwrap());
+}
+
+#[actix_web::test]
+async fn admins_manage_roles_and_disable_users() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/admin/accountants")
+        .insert_header(bearer(&f.admin_token))
+        .set_json(json!({ "username": "dora", "password": "luna-de-invierno" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/admin/users/{}/role", f.bob_id))
+        .insert_header(bearer(&f.admin_token))
+        .set_json(json!({ "role": "accountant" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri("/admin/users")
+        .insert_header(bearer(&f.admin_token))
+        .to_request();
+    let users: Value = test::call_and_read_body_json(&app, req).await;
+    let roles: Vec<(&str, &str)> = users
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| (user["username"].as_str().unwrap(), user["role"].as_str().unwrap()))
+        .collect();
+    assert!(roles.contains(&("bob", "accountant")));
+    assert!(roles.contains(&("dora", "accountant")));
+
+    // Los clientes no pueden usar la administración
+    let req = test::TestRequest::get()
+        .uri("/admin/users")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let bob_token = body["token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/admin/users/{}/disable", f.bob_id))
+        .insert_header(bearer(&f.admin_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    // El token que ya tenía deja de valer y no puede volver a entrar
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&bob_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": PASSWORD }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "USER_DISABLED");
+}
+
+#[actix_web::test]
+async fn other_users_accounts_look_the_same_as_missing_ones() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let account_of = |token: &str| {
+        test::TestRequest::get()
+            .uri("/protected/balance")
+            .insert_header(bearer(token))
+            .to_request()
+    };
+    let alice: Value = test::call_and_read_body_json(&app, account_of(&f.alice_token)).await;
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let bob: Value = test::call_and_read_body_json(&app, account_of(body["token"].as_str().unwrap())).await;
+
+    let get = |token: &str, id: &Value| {
+        test::TestRequest::get()
+            .uri(&format!("/protected/accounts/{}", id))
+            .insert_header(bearer(token))
+            .to_request()
+    };
+    let resp = test::call_service(&app, get(&f.alice_token, &alice["id"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::call_service(&app, get(&f.alice_token, &bob["id"])).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let foreign: Value = test::read_body_json(resp).await;
+    let resp = test::call_service(&app, get(&f.alice_token, &json!(9999))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let missing: Value = test::read_body_json(resp).await;
+    assert_eq!(foreign["code"], "ACCOUNT_NOT_FOUND");
+    assert_eq!(foreign, missing);
+
+    // Con `account:read_any` sí se ve la cuenta ajena
+    let body: Value = test::call_and_read_body_json(&app, get(&f.accountant_token, &bob["id"])).await;
+    assert_eq!(body["user_id"], f.bob_id);
+}
+
+#[actix_web::test]
+async fn admins_reset_credentials_with_a_temporary_password() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": PASSWORD }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let bob_token = body["token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/setup")
+        .insert_header(bearer(&bob_token))
+        .to_request();
+    let setup: Value = test::call_and_read_body_json(&app, req).await;
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/enable")
+        .insert_header(bearer(&bob_token))
+        .set_json(json!({ "code": totp_code(setup["secret"].as_str().unwrap()) }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/admin/users/{}/reset-credentials", f.bob_id))
+        .insert_header(bearer(&f.admin_token))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let temporary_password = body["temporary_password"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": PASSWORD }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": temporary_password }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    // El reseteo también quita la verificación en dos pasos: el login se completa sin desafío
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["token"].is_string(), "{}", body);
+    assert!(body.get("challenge").is_none());
+}
+
+#[actix_web::test]
+async fn registration_requires_a_valid_invite_and_a_strong_password() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/invites")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    let invite: Value = test::call_and_read_body_json(&app, req).await;
+    let code = invite["code"].as_str().unwrap().to_string();
+
+    let cases = [
+        (json!({ "username": "erin", "password": "sol-de-mediodia" }), "INVALID_INVITE_CODE"),
+        (json!({ "username": "erin", "password": "corta", "invite_code": code }), "WEAK_PASSWORD"),
+        (json!({ "username": "erin", "password": "password123", "invite_code": code }), "WEAK_PASSWORD"),
+        (json!({ "username": "1erin", "password": "sol-de-mediodia", "invite_code": code }), "INVALID_REQUEST"),
+        (json!({ "username": "bob", "password": "sol-de-mediodia", "invite_code": code }), "USERNAME_TAKEN"),
+    ];
+    for (payload, expected) in cases {
+        let req = test::TestRequest::post()
+            .uri("/auth/register")
+            .set_json(&payload)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], expected, "{}", payload);
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "username": "erin", "password": "sol-de-mediodia", "invite_code": code }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+
+    // Cada invitación sirve una sola vez
+    let req = test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "username": "frank", "password": "sol-de-mediodia", "invite_code": code }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "INVALID_INVITE_CODE");
+}
+
+#[actix_web::test]
+async fn changing_the_password_closes_every_session() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/password")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(json!({ "current_password": "otra", "new_password": "sol-de-mediodia" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/password")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(json!({ "current_password": PASSWORD, "new_password": "sol-de-mediodia" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": "sol-de-mediodia" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri("/protected/balance")
+        .insert_header(bearer(&token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn password_reset_tokens_are_single_use() {
+    let f = fixture().await;
+    let notifier = MemoryNotifier::new();
+    let app = init_app!(f.store, notifier);
+
+    // Un usuario inexistente recibe la misma respuesta, pero no se envía nada
+    for username in ["nadie", "bob"] {
+        let req = test::TestRequest::post()
+            .uri("/auth/password/forgot")
+            .set_json(json!({ "username": username }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::ACCEPTED);
+    }
+
+    let sent = notifier.sent();
+    assert_eq!(sent.len(), 1);
+    let Notification::PasswordReset { username, token, .. } = &sent[0];
+    assert_eq!(username, "bob");
+
+    let req = test::TestRequest::post()
+        .uri("/auth/password/reset")
+        .set_json(json!({ "token": token, "new_password": "luna-de-invierno" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/password/reset")
+        .set_json(json!({ "token": token, "new_password": "otra-luna-nueva" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "INVALID_RESET_TOKEN");
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "bob", "password": "luna-de-invierno" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+// Notificador caído, como un webhook que no responde
+#[derive(Clone)]
+struct FailingNotifier;
+
+#[async_trait::async_trait]
+impl Notifier for FailingNotifier {
+    async fn send(&self, _: &Notification) -> Result<(), BankError> {
+        Err(BankError::Internal("webhook caído".to_string()))
+    }
+}
+
+#[actix_web::test]
+async fn password_reset_requests_are_throttled_and_never_reveal_the_user() {
+    let f = fixture().await;
+    let app = init_app!(f.store, FailingNotifier);
+
+    let forgot = |username: &str| {
+        test::TestRequest::post()
+            .uri("/auth/password/forgot")
+            .set_json(json!({ "username": username }))
+            .to_request()
+    };
+
+    // Aunque el notificador falle, un usuario que existe recibe la misma respuesta
+    for _ in 0..4 {
+        assert_eq!(test::call_service(&app, forgot("bob")).await.status(), StatusCode::ACCEPTED);
+    }
+    let resp = test::call_service(&app, forgot("bob")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("Retry-After"));
+
+    // El límite es por nombre, exista o no el usuario
+    for _ in 0..4 {
+        assert_eq!(test::call_service(&app, forgot("nadie")).await.status(), StatusCode::ACCEPTED);
+    }
+    assert_eq!(test::call_service(&app, forgot("nadie")).await.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_web::test]
+async fn wrong_current_passwords_count_towards_the_login_lock() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let change = |current_password: &str| {
+        test::TestRequest::post()
+            .uri("/protected/password")
+            .insert_header(bearer(&f.alice_token))
+            .set_json(json!({ "current_password": current_password, "new_password": "sol-de-mediodia" }))
+            .to_request()
+    };
+    for _ in 0..4 {
+        assert_eq!(test::call_service(&app, change("otra")).await.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Bloqueada, ni siquiera la contraseña correcta pasa
+    assert_eq!(test::call_service(&app, change(PASSWORD)).await.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "alice", "password": PASSWORD }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/auth-events")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let events: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(events.as_array().unwrap().iter().any(|event| event["event"] == "password_change_failed"));
+}
+
+#[actix_web::test]
+async fn accountants_must_enrol_in_two_factor_when_the_policy_requires_it() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::put()
+        .uri("/admin/roles/accountant/two-factor")
+        .insert_header(bearer(&f.admin_token))
+        .set_json(json!({ "required": true }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "TWO_FACTOR_ENROLLMENT_REQUIRED");
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/setup")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let setup: Value = test::call_and_read_body_json(&app, req).await;
+    let secret = setup["secret"].as_str().unwrap().to_string();
+    assert!(setup["provisioning_uri"].as_str().unwrap().starts_with("otpauth://totp/FriendBank:contador?"));
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/enable")
+        .insert_header(bearer(&f.accountant_token))
+        .set_json(json!({ "code": totp_code(&secret) }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let recovery_code = body["recovery_codes"][0].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/ledger/check")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    // Ahora la contraseña solo da un desafío
+    let login = || {
+        test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": "contador", "password": PASSWORD }))
+            .to_request()
+    };
+    let body: Value = test::call_and_read_body_json(&app, login()).await;
+    assert_eq!(body["two_factor_required"], true);
+    assert!(body.get("token").is_none());
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login/2fa")
+        .set_json(json!({ "challenge": challenge, "code": "000000" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login/2fa")
+        .set_json(json!({ "challenge": challenge, "code": recovery_code }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body["token"].is_string());
+
+    // Los códigos de recuperación son de un solo uso
+    let body: Value = test::call_and_read_body_json(&app, login()).await;
+    let req = test::TestRequest::post()
+        .uri("/auth/login/2fa")
+        .set_json(json!({ "challenge": body["challenge"], "code": recovery_code }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn repeated_login_failures_lock_the_username_and_are_audited() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let login = |password: &str| {
+        test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": "bob", "password": password }))
+            .to_request()
+    };
+
+    // Los primeros fallos solo cuentan; el cuarto ya bloquea unos segundos
+    for _ in 0..4 {
+        let resp = test::call_service(&app, login("incorrecta")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Mientras dura el bloqueo ni la contraseña correcta sirve
+    let resp = test::call_service(&app, login(PASSWORD)).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = resp.headers().get("Retry-After").unwrap().to_str().unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 2);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "TOO_MANY_ATTEMPTS");
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/auth-events")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let events: Value = test::call_and_read_body_json(&app, req).await;
+    let events = events.as_array().unwrap();
+    assert_eq!(events[0]["event"], "login_blocked");
+    assert_eq!(events[0]["username"], "bob");
+    assert_eq!(events[1]["event"], "login_failed");
+    assert_eq!(events[1]["user_id"], f.bob_id);
+    assert_eq!(events[1]["details"]["failures"], 4);
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/auth-events")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn wrong_two_factor_codes_count_towards_the_login_lock() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/setup")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    let setup: Value = test::call_and_read_body_json(&app, req).await;
+    let secret = setup["secret"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/enable")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(json!({ "code": totp_code(&secret) }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let login = || {
+        test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": "alice", "password": PASSWORD }))
+            .to_request()
+    };
+    let wrong_code = |challenge: &Value| {
+        test::TestRequest::post()
+            .uri("/auth/login/2fa")
+            .set_json(json!({ "challenge": challenge, "code": "000000" }))
+            .to_request()
+    };
+
+    // La contraseña correcta no borra los fallos del segundo factor: el cuarto bloquea
+    for _ in 0..2 {
+        let body: Value = test::call_and_read_body_json(&app, login()).await;
+        for _ in 0..2 {
+            let resp = test::call_service(&app, wrong_code(&body["challenge"])).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    let resp = test::call_service(&app, login()).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_web::test]
+async fn wrong_codes_when_disabling_two_factor_count_towards_the_login_lock() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/setup")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    let setup: Value = test::call_and_read_body_json(&app, req).await;
+    let secret = setup["secret"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::post()
+        .uri("/protected/2fa/enable")
+        .insert_header(bearer(&f.alice_token))
+        .set_json(json!({ "code": totp_code(&secret) }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let disable = |code: &str| {
+        test::TestRequest::post()
+            .uri("/protected/2fa/disable")
+            .insert_header(bearer(&f.alice_token))
+            .set_json(json!({ "password": PASSWORD, "code": code }))
+            .to_request()
+    };
+    for _ in 0..4 {
+        assert_eq!(test::call_service(&app, disable("000000")).await.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Un token robado no sirve para seguir probando códigos
+    let resp = test::call_service(&app, disable(&totp_code(&secret))).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_web::test]
+async fn login_failures_use_the_client_ip_forwarded_by_a_trusted_proxy() {
+    let f = fixture().await;
+    let mut config = test_config();
+    config.server.trusted_proxies = vec!["10.0.0.1".parse().unwrap()];
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+
+    let failed_login = |peer: &str, forwarded_for: Option<&str>| {
+        let mut req = test::TestRequest::post()
+            .uri("/auth/login")
+            .peer_addr(peer.parse().unwrap())
+            .set_json(json!({ "username": "bob", "password": "incorrecta" }));
+        if let Some(forwarded_for) = forwarded_for {
+            req = req.insert_header(("X-Forwarded-For", forwarded_for));
+        }
+        req.to_request()
+    };
+
+    // La primera IP la escribió el propio cliente; solo vale la que añadió el proxy
+    let attempts = [
+        ("10.0.0.1:4000", Some("198.51.100.9, 203.0.113.7")),
+        ("10.0.0.1:4000", None),
+        ("192.0.2.1:4000", Some("203.0.113.7")),
+    ];
+    for (peer, forwarded_for) in attempts {
+        let resp = test::call_service(&app, failed_login(peer, forwarded_for)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/accountant/auth-events")
+        .insert_header(bearer(&f.accountant_token))
+        .to_request();
+    let events: Value = test::call_and_read_body_json(&app, req).await;
+    let ips: Vec<&Value> = events.as_array().unwrap().iter().map(|event| &event["ip_address"]).collect();
+    assert_eq!(ips, [&json!("192.0.2.1"), &Value::Null, &json!("203.0.113.7")]);
+}
+
+#[actix_web::test]
+async fn users_list_and_terminate_their_sessions() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let login = |user_agent: &str| {
+        test::TestRequest::post()
+            .uri("/auth/login")
+            .insert_header(("User-Agent", user_agent.to_string()))
+            .set_json(json!({ "username": "bob", "password": PASSWORD }))
+            .to_request()
+    };
+    let phone: Value = test::call_and_read_body_json(&app, login("Telefono/1.0")).await;
+    let laptop: Value = test::call_and_read_body_json(&app, login("Portatil/2.0")).await;
+    let phone_token = phone["token"].as_str().unwrap().to_string();
+    let laptop_token = laptop["token"].as_str().unwrap().to_string();
+
+    let list = |token: &str| {
+        test::TestRequest::get()
+            .uri("/protected/sessions")
+            .insert_header(bearer(token))
+            .to_request()
+    };
+    let sessions: Value = test::call_and_read_body_json(&app, list(&laptop_token)).await;
+    let sessions = sessions.as_array().unwrap().clone();
+    assert_eq!(sessions.len(), 2);
+    let phone_session = sessions.iter().find(|s| s["user_agent"] == "Telefono/1.0").unwrap();
+    assert_eq!(phone_session["current"], false);
+    assert!(sessions.iter().any(|s| s["user_agent"] == "Portatil/2.0" && s["current"] == true));
+
+    // Las sesiones de otro usuario no existen para alice
+    let req = test::TestRequest::delete()
+        .uri(&format!("/protected/sessions/{}", phone_session["id"].as_str().unwrap()))
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    // Cerrar la sesión del teléfono invalida su access token y su refresh token
+    let req = test::TestRequest::delete()
+        .uri(&format!("/protected/sessions/{}", phone_session["id"].as_str().unwrap()))
+        .insert_header(bearer(&laptop_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+
+    assert_eq!(test::call_service(&app, list(&phone_token)).await.status(), StatusCode::UNAUTHORIZED);
+    let req = test::TestRequest::post()
+        .uri("/auth/refresh")
+        .set_json(json!({ "refresh_token": phone["refresh_token"] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let sessions: Value = test::call_and_read_body_json(&app, list(&laptop_token)).await;
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+
+    // Cerrar sesión en todas partes también cierra la actual
+    let req = test::TestRequest::delete()
+        .uri("/protected/sessions")
+        .insert_header(bearer(&laptop_token))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    assert_eq!(test::call_service(&app, list(&laptop_token)).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn jwks_is_public_and_never_exposes_the_shared_secret() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::get().uri("/.well-known/jwks.json").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("Cache-Control").unwrap().to_str().unwrap().contains("max-age=300"));
+
+    // Las pruebas firman con JWT_SECRET, que nunca se publica
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body, json!({ "keys": [] }));
+}
+
+#[actix_web::test]
+async fn business_limits_and_token_lifetimes_come_from_the_config() {
+    let f = fixture().await;
+    let mut config = test_config();
+    config.registration.require_invite_code = false;
+    config.registration.max_active_invites = 1;
+    config.auth.access_token_ttl_minutes = 15;
+    config.limits.max_amount = dec!(50);
+    config.limits.default_page_size = 1;
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/register")
+        .set_json(json!({ "username": "erin", "password": "sol-de-mediodia" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+
+    let invite = || {
+        test::TestRequest::post()
+            .uri("/protected/invites")
+            .insert_header(bearer(&f.alice_token))
+            .to_request()
+    };
+    assert_eq!(test::call_service(&app, invite()).await.status(), StatusCode::CREATED);
+    assert_eq!(test::call_service(&app, invite()).await.status(), StatusCode::BAD_REQUEST);
+
+    // Pedidas a la vez, el límite se cuenta con la fila del creador bloqueada: solo pasa una
+    let invites = (0..5).map(|_| {
+        let req = test::TestRequest::post()
+            .uri("/protected/invites")
+            .insert_header(bearer(&f.accountant_token))
+            .to_request();
+        test::call_service(&app, req)
+    });
+    let created = join_all(invites)
+        .await
+        .iter()
+        .filter(|resp| resp.status() == StatusCode::CREATED)
+        .count();
+    assert_eq!(created, 1);
+
+    let req = test::TestRequest::post()
+        .uri("/auth/login")
+        .set_json(json!({ "username": "erin", "password": "sol-de-mediodia" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["expires_in"], 900);
+
+    let transfer = |amount: &str| {
+        test::TestRequest::post()
+            .uri("/protected/transfer")
+            .insert_header(bearer(&f.alice_token))
+            .set_json(json!({ "recipient_username": "bob", "amount": amount }))
+            .to_request()
+    };
+    let resp = test::call_service(&app, transfer("50.01")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "INVALID_AMOUNT");
+    assert_eq!(test::call_service(&app, transfer("50")).await.status(), StatusCode::OK);
+
+    // El depósito del fixture y la transferencia, de uno en uno
+    let req = test::TestRequest::get()
+        .uri("/protected/transactions")
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert!(body["next_cursor"].is_number());
+}
+
+// Almacenamiento sin base de datos detrás, como un MySQL caído
+#[derive(Clone)]
+struct UnavailableStore;
+
+#[async_trait::async_trait]
+impl Store for UnavailableStore {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork>, BankError> {
+        Err(BankError::Internal("connection refused".to_string()))
+    }
+}
+
+#[actix_web::test]
+async fn health_probes_report_each_check_with_its_latency() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["checks"]["process"]["status"], "ok");
+
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    for check in ["database", "migrations", "singletons"] {
+        assert_eq!(body["checks"][check]["status"], "ok", "{}", check);
+        assert!(body["checks"][check]["latency_ms"].is_number(), "{}", check);
+    }
+
+    // Sin base de datos el proceso sigue vivo pero deja de estar listo
+    let app = init_app!(UnavailableStore);
+
+    let req = test::TestRequest::get().uri("/healthz").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get().uri("/readyz").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "failed");
+    assert_eq!(body["checks"]["database"]["status"], "failed");
+    assert_eq!(body["checks"]["migrations"]["status"], "failed");
+    // El detalle del error no se expone
+    assert!(!body.to_string().contains("connection refused"));
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_money_movements_and_failed_logins() {
+    let f = fixture().await;
+    let mut config = test_config();
+    config.server.metrics_token = Some(METRICS_TOKEN.to_string());
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+
+    for amount in ["30.00", "500.00"] {
+        let req = test::TestRequest::post()
+            .uri("/protected/transfer")
+            .insert_header(bearer(&f.alice_token))
+            .set_json(json!({ "recipient_username": "bob", "amount": amount }))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+
+    // El cuarto fallo bloquea: el quinto intento ya no se comprueba
+    for _ in 0..5 {
+        let req = test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": "alice", "password": "otra" }))
+            .to_request();
+        test::call_service(&app, req).await;
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/protected/accounts/{}", f.bob_id))
+        .insert_header(bearer(&f.alice_token))
+        .to_request();
+    test::call_service(&app, req).await;
+
+    let req = test::TestRequest::get().uri("/metrics").insert_header(bearer(METRICS_TOKEN)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("Content-Type").unwrap().to_str().unwrap().starts_with("text/plain"));
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+    for line in [
+        r#"bank_operations_total{operation="transfer",outcome="success"} 1"#,
+        r#"bank_operations_total{operation="transfer",outcome="insufficient_funds"} 1"#,
+        r#"bank_operation_amount_total{operation="transfer",outcome="success"} 30"#,
+        r#"auth_failed_logins_total{reason="invalid_credentials"} 4"#,
+        r#"auth_failed_logins_total{reason="throttled"} 1"#,
+        r#"http_requests_total{method="POST",route="/protected/transfer",status="200"} 1"#,
+    ] {
+        assert!(body.lines().any(|l| l == line), "falta `{}` en:\n{}", line, body);
+    }
+    // Las rutas con parámetros se agrupan por su patrón
+    assert!(body.contains(r#"route="/protected/accounts/{id}""#), "{}", body);
+    assert!(!body.contains(&format!("/protected/accounts/{}", f.bob_id)));
+}
+
+#[actix_web::test]
+async fn metrics_require_the_configured_token_and_stay_hidden_in_production_without_one() {
+    let f = fixture().await;
+
+    // Producción sin token: el endpoint no existe
+    let app = init_app!(f.store);
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    // Con token se exige siempre, también fuera de producción
+    let mut config = test_config();
+    config.server.environment = Environment::Development;
+    config.server.metrics_token = Some(METRICS_TOKEN.to_string());
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+    for header in [None, Some(bearer("otro-token")), Some(bearer(&f.alice_token))] {
+        let mut req = test::TestRequest::get().uri("/metrics");
+        if let Some(header) = header {
+            req = req.insert_header(header);
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let req = test::TestRequest::get().uri("/metrics").insert_header(bearer(METRICS_TOKEN)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    // Fuera de producción y sin token queda abierto para el desarrollo local
+    let mut config = test_config();
+    config.server.environment = Environment::Development;
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn openapi_document_describes_the_api_and_swagger_ui_stays_out_of_production() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::get().uri("/openapi.json").to_request();
+    let spec: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(spec["paths"]["/protected/transfer"]["post"].is_object());
+    assert!(spec["paths"]["/auth/login"]["post"].is_object());
+    for schema in ["UserData", "TransactionData", "AccountantData", "ProblemDetails"] {
+        assert!(spec["components"]["schemas"][schema].is_object(), "{}", schema);
+    }
+    assert_eq!(spec["components"]["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+    // Las rutas protegidas documentan los errores de autenticación
+    let transfer = &spec["paths"]["/protected/transfer"]["post"];
+    assert!(transfer["responses"]["401"].is_object());
+    assert!(transfer["security"].is_array());
+
+    // Por defecto la configuración es de producción
+    let req = test::TestRequest::get().uri("/docs").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    let req = test::TestRequest::get().uri("/docs/swagger-ui-bundle.js").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    let mut config = test_config();
+    config.server.environment = Environment::Development;
+    let app = init_app!(f.store, MemoryNotifier::new(), config);
+
+    let req = test::TestRequest::get().uri("/docs").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("/openapi.json"));
+    assert!(!body.contains("https://"));
+
+    // Los ficheros de Swagger UI salen de la propia API
+    for (file, content_type) in [("swagger-ui.css", "text/css"), ("swagger-ui-bundle.js", "text/javascript")] {
+        let req = test::TestRequest::get().uri(&format!("/docs/{}", file)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("content-type").unwrap(), content_type);
+    }
+    let req = test::TestRequest::get().uri("/docs/no-existe.js").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn every_response_carries_the_request_id() {
+    let f = fixture().await;
+    let app = init_app!(f.store);
+
+    let req = test::TestRequest::get()
+        .uri("/healthz")
+        .insert_header(("X-Request-Id", "lb-7f3a.42"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Request-Id").unwrap(), "lb-7f3a.42");
+
+    // Sin cabecera, o con una que no es segura para el log, se genera uno nuevo
+    for header in [None, Some("dos palabras")] {
+        let mut req = test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(json!({ "username": "alice", "password": "otra" }));
+        if let Some(value) = header {
+            req = req.insert_header(("X-Request-Id", value));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let request_id = resp.headers().get("X-Request-Id").unwrap().to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(request_id).is_ok(), "{}", request_id);
+    }
+}
+
+#[actix_web::test]
+async fn the_serverless_entrypoint_serves_the_same_app() {
+    let f = fixture().await;
+    let app = ServerlessApp::start(
+        AppState::new(Arc::new(f.store.clone()), Arc::new(MemoryNotifier::new()), test_config()).unwrap(),
+    );
+
+    // Así llega a la función: URL absoluta, cuerpo como texto y la IP en `x-real-ip`
+    let vercel_request = |method: http::Method, path: &str, body: Body| {
+        let mut req = Request::new(body);
+        *req.method_mut() = method;
+        *req.uri_mut() = format!("https://friendbank.vercel.app{}", path).parse().unwrap();
+        req.headers_mut().insert("x-real-ip", "203.0.113.9".parse().unwrap());
+        req
+    };
+    let json_body = |resp: &vercel_runtime::Response<Body>| -> Value {
+        match resp.body() {
+            Body::Binary(bytes) => serde_json::from_slice(bytes).unwrap(),
+            other => panic!("cuerpo inesperado: {:?}", other),
+        }
+    };
+
+    let resp = app.handle(vercel_request(http::Method::GET, "/healthz", Body::Empty)).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK.as_u16());
+    assert!(resp.headers().contains_key("x-request-id"));
+    assert_eq!(json_body(&resp)["status"], "ok");
+
+    let mut req = vercel_request(
+        http::Method::POST,
+        "/auth/login",
+        Body::Text(json!({ "username": "bob", "password": "incorrecta" }).to_string()),
+    );
+    req.headers_mut().insert("content-type", "application/json".parse().unwrap());
+    let resp = app.handle(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED.as_u16());
+    assert_eq!(json_body(&resp)["code"], "INVALID_CREDENTIALS");
+
+    // El fallo queda registrado con la IP que mandó el proxy
+    let mut req = vercel_request(http::Method::GET, "/accountant/auth-events?limit=1", Body::Empty);
+    let (name, value) = bearer(&f.accountant_token);
+    req.headers_mut().insert(name, value.parse().unwrap());
+    let resp = app.handle(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK.as_u16());
+    let events = json_body(&resp);
+    assert_eq!(events[0]["event"], "login_failed");
+    assert_eq!(events[0]["ip_address"], "203.0.113.9");
+}